@@ -1,4 +1,4 @@
-use std::{fs, path::PathBuf};
+use std::{fs, path::PathBuf, time::Duration};
 
 use serde::{Deserialize, Serialize};
 use tokio::{
@@ -6,7 +6,14 @@ use tokio::{
     io::{AsyncReadExt, AsyncWriteExt},
 };
 
-use crate::source_provider::{FetchMode, DynSource};
+use crate::{
+    cache_warmer::WarmerOptions,
+    custom_provider,
+    ddragon::Language,
+    http_client::{HttpClient, HttpOptions},
+    source_provider::{DynSource, FetchMode, GameMode},
+    static_data::Routing,
+};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AppConfig {
@@ -22,6 +29,49 @@ pub struct AppConfig {
     #[serde(default)]
     mini_mode: bool,
 
+    #[serde(default = "AppConfig::default_cache_size_limit_mb")]
+    cache_size_limit_mb: u64,
+
+    /// Locale of ddragon data like `zh_CN`, or `auto` to follow the client.
+    #[serde(default = "AppConfig::default_language")]
+    language: String,
+
+    #[serde(default = "AppConfig::default_http_timeout_secs")]
+    http_timeout_secs: u64,
+
+    #[serde(default = "AppConfig::default_http_max_retries")]
+    http_max_retries: u32,
+
+    #[serde(default = "AppConfig::default_http_requests_per_second")]
+    http_requests_per_second: u32,
+
+    /// Empty to use the system proxy.
+    #[serde(default)]
+    proxy: String,
+
+    #[serde(default)]
+    no_proxy: Vec<String>,
+
+    #[serde(default)]
+    extra_ca_certs: Vec<PathBuf>,
+
+    /// Backend of each kind of static data, like `{"Queues": "CommunityDragon"}`.
+    #[serde(default)]
+    static_data: Routing,
+
+    /// Where custom builds are kept, `custom_builds` in the data path if `None`.
+    #[serde(default)]
+    custom_builds_path: Option<PathBuf>,
+
+    #[serde(default = "AppConfig::default_warmer_modes")]
+    warmer_modes: Vec<String>,
+
+    #[serde(default = "AppConfig::default_warmer_concurrency")]
+    warmer_concurrency: usize,
+
+    #[serde(default = "AppConfig::default_warmer_interval_ms")]
+    warmer_interval_ms: u64,
+
     #[serde(skip_deserializing)]
     data_path: PathBuf,
     #[serde(skip_deserializing)]
@@ -38,6 +88,19 @@ impl AppConfig {
             auto_select_lane: false,
             expired_days: AppConfig::default_expired(),
             mini_mode: false,
+            cache_size_limit_mb: AppConfig::default_cache_size_limit_mb(),
+            language: AppConfig::default_language(),
+            http_timeout_secs: AppConfig::default_http_timeout_secs(),
+            http_max_retries: AppConfig::default_http_max_retries(),
+            http_requests_per_second: AppConfig::default_http_requests_per_second(),
+            proxy: String::new(),
+            no_proxy: vec![],
+            extra_ca_certs: vec![],
+            static_data: Routing::default(),
+            custom_builds_path: None,
+            warmer_modes: AppConfig::default_warmer_modes(),
+            warmer_concurrency: AppConfig::default_warmer_concurrency(),
+            warmer_interval_ms: AppConfig::default_warmer_interval_ms(),
             cache_path: proj_dir.cache_dir().to_owned(),
             config_path: proj_dir.config_dir().to_owned(),
             data_path: proj_dir.data_dir().to_owned(),
@@ -48,6 +111,77 @@ impl AppConfig {
         3
     }
 
+    pub fn default_cache_size_limit_mb() -> u64 {
+        200
+    }
+
+    pub fn default_language() -> String {
+        String::from("auto")
+    }
+
+    /// `None` if it follows the client.
+    pub fn language(&self) -> Option<Language> {
+        Language::from_locale(&self.language)
+    }
+
+    pub fn default_http_timeout_secs() -> u64 {
+        15
+    }
+
+    pub fn default_http_max_retries() -> u32 {
+        3
+    }
+
+    pub fn default_http_requests_per_second() -> u32 {
+        5
+    }
+
+    pub fn http_options(&self) -> HttpOptions {
+        HttpOptions {
+            timeout: Duration::from_secs(self.http_timeout_secs),
+            max_retries: self.http_max_retries,
+            requests_per_second: self.http_requests_per_second,
+            proxy: match self.proxy.trim() {
+                "" => None,
+                proxy => Some(proxy.to_owned()),
+            },
+            no_proxy: self.no_proxy.clone(),
+            ca_certs: self.extra_ca_certs.clone(),
+        }
+    }
+
+    pub fn default_warmer_modes() -> Vec<String> {
+        vec![GameMode::Classic.to_string(), GameMode::Aram.to_string()]
+    }
+
+    pub fn default_warmer_concurrency() -> usize {
+        2
+    }
+
+    pub fn default_warmer_interval_ms() -> u64 {
+        1500
+    }
+
+    pub fn static_data_routing(&self) -> Routing {
+        self.static_data.clone()
+    }
+
+    pub fn custom_builds_dir(&self) -> PathBuf {
+        match &self.custom_builds_path {
+            Some(path) if !path.as_os_str().is_empty() => path.clone(),
+            // `data_path` is not sent back by the window, so it can't be used here.
+            _ => custom_provider::default_dir(),
+        }
+    }
+
+    pub fn warmer_options(&self) -> WarmerOptions {
+        WarmerOptions {
+            modes: self.warmer_modes.iter().map(|m| GameMode::from_str(m)).collect(),
+            concurrency: self.warmer_concurrency.max(1),
+            interval: Duration::from_millis(self.warmer_interval_ms),
+        }
+    }
+
     pub fn init_meta(&mut self) {
         let proj_dir = directories::ProjectDirs::from("com", "Jinte", "Lola").unwrap();
         self.cache_path = proj_dir.cache_dir().to_owned();
@@ -95,8 +229,12 @@ impl AppConfig {
         }
     }
 
-    pub async fn invoke(&self, provider: &mut DynSource) {
+    pub async fn invoke(&self, provider: &DynSource, http: &HttpClient) -> Result<(), String> {
         provider.set_fetch_mode(self.fetch_mode);
         provider.set_expired(self.expired_days).await;
+        provider
+            .set_cache_size_limit(self.cache_size_limit_mb * 1024 * 1024)
+            .await;
+        http.configure(self.http_options())
     }
 }
@@ -1,12 +1,19 @@
 use serde::{Deserialize, Serialize};
 
+use crate::source_provider::schema::DriftReport;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AppStatus {
     pub lcu_loaded: bool,
+    /// Last provider payload that didn't match its schema.
+    pub schema_drift: Option<DriftReport>,
 }
 
 impl AppStatus {
     pub fn default() -> Self {
-        AppStatus { lcu_loaded: false }
+        AppStatus {
+            lcu_loaded: false,
+            schema_drift: None,
+        }
     }
 }
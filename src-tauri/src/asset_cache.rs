@@ -0,0 +1,182 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use directories::ProjectDirs;
+use tauri::http::{Request, Response, ResponseBuilder};
+use tracing::{error, info};
+
+use crate::{
+    ddragon::patch_of, http_client::HttpClient, source_provider::in_flight::InFlight,
+    util::write_atomic,
+};
+
+pub const ASSET_SCHEME: &str = "lola-asset";
+/// Path prefix of assets and where they are downloaded from.
+const ASSET_ORIGINS: [(&str, &str); 2] = [
+    ("cdn/", "https://ddragon.leagueoflegends.com/cdn/"),
+    ("cdragon/", "https://raw.communitydragon.org/"),
+];
+/// How long a request waits for a missing asset, the download goes on after it.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Images of ddragon and CommunityDragon saved under the same path as asked, like
+/// `cdn/13.5.1/img/champion/Ahri.png`, and downloaded the first time they are asked.
+pub struct AssetCache {
+    dir: PathBuf,
+    http: HttpClient,
+    /// Downloads by asset path, an image asked again while downloading joins the running one.
+    downloads: Arc<InFlight<Vec<u8>>>,
+}
+
+/// Url the window loads the asset at `path` with.
+pub fn asset_url(path: &str) -> String {
+    // Webview2 only allows custom schemes in the form of `https://<scheme>.localhost`.
+    if cfg!(windows) {
+        format!("https://{}.localhost/{}", ASSET_SCHEME, path)
+    } else {
+        format!("{}://localhost/{}", ASSET_SCHEME, path)
+    }
+}
+
+impl AssetCache {
+    pub fn initialize(http: HttpClient) -> Self {
+        if let Some(proj) = ProjectDirs::from("com", "Jinte", "Lola") {
+            let dir = proj.cache_dir().join("assets");
+            std::fs::create_dir_all(&dir).unwrap();
+            return AssetCache {
+                dir,
+                http,
+                downloads: Arc::new(InFlight::new()),
+            };
+        }
+        panic!("Can't initialize!")
+    }
+
+    /// Handler of the `lola-asset` protocol, a missing asset is downloaded while the
+    /// request waits, up to `DOWNLOAD_TIMEOUT`.
+    pub fn handle(&self, request: &Request) -> Result<Response, Box<dyn std::error::Error>> {
+        let path = match asset_path(request.uri()) {
+            Some(path) => path,
+            None => return ResponseBuilder::new().status(400).body(Vec::new()),
+        };
+        let bytes = match std::fs::read(self.dir.join(&path)) {
+            Ok(bytes) => Ok(bytes),
+            Err(_) => self.download(&path),
+        };
+        match bytes {
+            Ok(bytes) => ResponseBuilder::new()
+                .status(200)
+                .mimetype(mime_of(&path))
+                .header("Cache-Control", "max-age=31536000, immutable")
+                .body(bytes),
+            Err(err) => {
+                error!("Get asset {} failed: {}", path, err);
+                ResponseBuilder::new()
+                    .status(404)
+                    .header("Cache-Control", "no-store")
+                    .body(Vec::new())
+            }
+        }
+    }
+
+    /// Download the asset at `path`, once however often it is asked at the same time.
+    fn download(&self, path: &str) -> Result<Vec<u8>, String> {
+        let url = ASSET_ORIGINS
+            .iter()
+            .find_map(|(prefix, origin)| Some(format!("{}{}", origin, path.strip_prefix(prefix)?)))
+            .ok_or_else(|| format!("No origin of asset {}", path))?;
+        let file = self.dir.join(path);
+        let http = self.http.clone();
+        let downloads = self.downloads.clone();
+        let key = path.to_string();
+        // Spawned so the download goes on when the request stops waiting.
+        let task = tauri::async_runtime::spawn(async move {
+            let download = async move {
+                let bytes = http.get_bytes(&url).await?;
+                tokio::fs::create_dir_all(file.parent().unwrap())
+                    .await
+                    .map_err(|err| err.to_string())?;
+                // Served as immutable, so a half written image must never be read.
+                write_atomic(&file, &bytes, false).await?;
+                Ok(bytes)
+            };
+            downloads.run(key, download).await
+        });
+        tauri::async_runtime::block_on(tokio::time::timeout(DOWNLOAD_TIMEOUT, task))
+            .map_err(|_| String::from("Timed out"))?
+            .map_err(|err| err.to_string())?
+    }
+
+    /// Remove assets of other ddragon versions and patches, unversioned ones like
+    /// rune icons are kept.
+    pub async fn prune(&self, version: &str) {
+        if version.is_empty() {
+            return;
+        }
+        self.prune_dir("cdn", &[version, "img"]).await;
+        self.prune_dir("cdragon", &[&patch_of(version)]).await;
+    }
+
+    async fn prune_dir(&self, dir: &str, keep: &[&str]) {
+        let mut dir = match tokio::fs::read_dir(self.dir.join(dir)).await {
+            Ok(dir) => dir,
+            Err(_) => return,
+        };
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if !keep.contains(&name.as_ref()) {
+                info!("Remove assets of {}", name);
+                if let Err(err) = tokio::fs::remove_dir_all(entry.path()).await {
+                    error!("{:?}", err);
+                }
+            }
+        }
+    }
+}
+
+/// Asset path of an asset url, `None` if it leaves the cache directory.
+fn asset_path(uri: &str) -> Option<String> {
+    let path = uri
+        .split_once("://")?
+        .1
+        .split_once('/')?
+        .1
+        .split(['?', '#'])
+        .next()?;
+    let path = percent_decode(path)?;
+    let is_plain = Path::new(&path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    let is_known = ASSET_ORIGINS
+        .iter()
+        .any(|(prefix, _)| path.starts_with(prefix));
+    (is_known && is_plain).then_some(path)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn mime_of(path: &str) -> &'static str {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
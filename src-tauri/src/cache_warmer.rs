@@ -0,0 +1,256 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use directories::ProjectDirs;
+use futures_util::{lock::Mutex, stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tracing::{error, info};
+
+use crate::{
+    champion_id::ChampionId,
+    ddragon::DDragon,
+    source_provider::{DynSource, FetchMode, GameMode, Lane},
+    util::write_atomic,
+};
+
+#[derive(Debug, Clone)]
+pub struct WarmerOptions {
+    pub modes: Vec<GameMode>,
+    pub concurrency: usize,
+    /// Pause of a worker after each download, to be polite to the provider.
+    pub interval: Duration,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmerProgress {
+    pub done: usize,
+    pub total: usize,
+    pub failed: usize,
+    pub running: bool,
+}
+
+/// Saved after each job, so a stopped run can resume where it stopped.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WarmerState {
+    modes: Vec<String>,
+    /// Jobs done on another patch have to be done again.
+    #[serde(default)]
+    patch: String,
+    done: HashSet<String>,
+}
+
+#[derive(Debug)]
+pub struct CacheWarmer {
+    state_file: PathBuf,
+    running: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+    progress: Arc<Mutex<WarmerProgress>>,
+}
+
+#[derive(Debug, Clone)]
+struct Job {
+    champion: ChampionId,
+    lane: Lane,
+    mode: GameMode,
+}
+
+impl Job {
+    fn all(champions: &[ChampionId], modes: &[GameMode]) -> Vec<Job> {
+        let mut jobs = Vec::new();
+        for champion in champions {
+            for mode in modes {
+                let lanes: &[Lane] = match mode {
+                    GameMode::Classic => &[
+                        Lane::Top,
+                        Lane::Jungle,
+                        Lane::Mid,
+                        Lane::Bot,
+                        Lane::Support,
+                    ],
+                    // Aram and Urf no need lane, the frontend always asks with top.
+                    GameMode::Aram | GameMode::Urf => &[Lane::Top],
+                };
+                for lane in lanes {
+                    jobs.push(Job {
+                        champion: champion.clone(),
+                        lane: *lane,
+                        mode: *mode,
+                    });
+                }
+            }
+        }
+        jobs
+    }
+
+    fn key(&self) -> String {
+        format!("{}-{:?}-{:?}", self.champion, self.lane, self.mode)
+    }
+}
+
+impl WarmerState {
+    async fn load(path: &Path) -> Self {
+        match tokio::fs::read_to_string(path).await {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_default(),
+            Err(_) => WarmerState::default(),
+        }
+    }
+
+    async fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        write_atomic(path, json.as_bytes(), false).await
+    }
+}
+
+impl CacheWarmer {
+    pub fn initialize() -> Self {
+        if let Some(proj) = ProjectDirs::from("com", "Jinte", "Lola") {
+            std::fs::create_dir_all(proj.cache_dir()).unwrap();
+            return CacheWarmer {
+                state_file: proj.cache_dir().join("warmer.json"),
+                running: Arc::new(AtomicBool::new(false)),
+                cancelled: Arc::new(AtomicBool::new(false)),
+                progress: Arc::new(Mutex::new(WarmerProgress::default())),
+            };
+        }
+        panic!("Can't initialize!")
+    }
+
+    pub async fn get_progress(&self) -> WarmerProgress {
+        self.progress.lock().await.clone()
+    }
+
+    pub fn start(&self, handle: AppHandle, options: WarmerOptions) -> Result<(), String> {
+        if let FetchMode::Offline = handle.state::<DynSource>().fetch_mode() {
+            return Err(String::from("Can't warm cache in offline mode!"));
+        }
+        let champions = handle.state::<DDragon>().get_champion_ids();
+        if champions.is_empty() {
+            // Like on a first start offline, a run with no jobs would look finished.
+            return Err(String::from(
+                "No champions loaded, try again once the game data is downloaded.",
+            ));
+        }
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err(String::from("Cache warmer is running already!"));
+        }
+        self.cancelled.store(false, Ordering::SeqCst);
+
+        let state_file = self.state_file.clone();
+        let running = self.running.clone();
+        let cancelled = self.cancelled.clone();
+        let progress = self.progress.clone();
+        tauri::async_runtime::spawn(async move {
+            run(&handle, &champions, options, &state_file, &cancelled, &progress).await;
+            running.store(false, Ordering::SeqCst);
+            let mut p = progress.lock().await;
+            p.running = false;
+            handle.emit_all("cache_warmer_progress", p.clone()).unwrap();
+        });
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        if self.running.load(Ordering::SeqCst) {
+            info!("Stopping cache warmer..");
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+async fn run(
+    handle: &AppHandle,
+    champions: &[ChampionId],
+    options: WarmerOptions,
+    state_file: &Path,
+    cancelled: &AtomicBool,
+    progress: &Mutex<WarmerProgress>,
+) {
+    let modes: Vec<String> = options.modes.iter().map(|m| m.to_string()).collect();
+    let ddragon = handle.state::<DDragon>();
+    let patch = ddragon.get_patch();
+    let mut state = WarmerState::load(state_file).await;
+    if state.modes != modes || state.patch != patch {
+        state = WarmerState {
+            modes,
+            patch,
+            done: HashSet::new(),
+        };
+    }
+
+    let jobs = Job::all(champions, &options.modes);
+    let total = jobs.len();
+    let pending: Vec<Job> = jobs
+        .into_iter()
+        .filter(|job| !state.done.contains(&job.key()))
+        .collect();
+    info!("Warm cache: {} of {} jobs pending.", pending.len(), total);
+    {
+        let mut p = progress.lock().await;
+        *p = WarmerProgress {
+            done: total - pending.len(),
+            total,
+            failed: 0,
+            running: true,
+        };
+        handle.emit_all("cache_warmer_progress", p.clone()).unwrap();
+    }
+
+    let state = Mutex::new(state);
+    stream::iter(pending)
+        .for_each_concurrent(options.concurrency, |job| {
+            let state = &state;
+            let interval = options.interval;
+            async move {
+                if cancelled.load(Ordering::SeqCst) {
+                    return;
+                }
+                let result = handle
+                    .state::<DynSource>()
+                    .warm_cache(&job.champion, job.lane, job.mode)
+                    .await;
+                let downloaded = match result {
+                    Ok(downloaded) => {
+                        let mut state = state.lock().await;
+                        state.done.insert(job.key());
+                        if let Err(err) = state.save(state_file).await {
+                            error!("Save warmer state failed: {}", err);
+                        }
+                        progress.lock().await.done += 1;
+                        downloaded
+                    }
+                    Err(err) => {
+                        error!("Warm {} failed: {}", job.key(), err);
+                        progress.lock().await.failed += 1;
+                        true
+                    }
+                };
+                let p = progress.lock().await.clone();
+                handle.emit_all("cache_warmer_progress", p).unwrap();
+                if downloaded {
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        })
+        .await;
+
+    let p = progress.lock().await.clone();
+    if p.done == p.total {
+        info!("Warm cache finished.");
+        if let Err(err) = tokio::fs::remove_file(state_file).await {
+            error!("Remove warmer state failed: {}", err);
+        }
+    } else {
+        info!(
+            "Warm cache stopped: {}/{} done, {} failed.",
+            p.done, p.total, p.failed
+        );
+    }
+}
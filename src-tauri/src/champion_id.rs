@@ -0,0 +1,389 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Known champions as `(lcu id, ddragon id, english name, op.gg slug)`.
+const CHAMPIONS: &[(i32, &str, &str, &str)] = &[
+    (1, "Annie", "Annie", "annie"),
+    (2, "Olaf", "Olaf", "olaf"),
+    (3, "Galio", "Galio", "galio"),
+    (4, "TwistedFate", "Twisted Fate", "twistedfate"),
+    (5, "XinZhao", "Xin Zhao", "xinzhao"),
+    (6, "Urgot", "Urgot", "urgot"),
+    (7, "Leblanc", "LeBlanc", "leblanc"),
+    (8, "Vladimir", "Vladimir", "vladimir"),
+    (9, "Fiddlesticks", "Fiddlesticks", "fiddlesticks"),
+    (10, "Kayle", "Kayle", "kayle"),
+    (11, "MasterYi", "Master Yi", "masteryi"),
+    (12, "Alistar", "Alistar", "alistar"),
+    (13, "Ryze", "Ryze", "ryze"),
+    (14, "Sion", "Sion", "sion"),
+    (15, "Sivir", "Sivir", "sivir"),
+    (16, "Soraka", "Soraka", "soraka"),
+    (17, "Teemo", "Teemo", "teemo"),
+    (18, "Tristana", "Tristana", "tristana"),
+    (19, "Warwick", "Warwick", "warwick"),
+    (20, "Nunu", "Nunu & Willump", "nunu"),
+    (21, "MissFortune", "Miss Fortune", "missfortune"),
+    (22, "Ashe", "Ashe", "ashe"),
+    (23, "Tryndamere", "Tryndamere", "tryndamere"),
+    (24, "Jax", "Jax", "jax"),
+    (25, "Morgana", "Morgana", "morgana"),
+    (26, "Zilean", "Zilean", "zilean"),
+    (27, "Singed", "Singed", "singed"),
+    (28, "Evelynn", "Evelynn", "evelynn"),
+    (29, "Twitch", "Twitch", "twitch"),
+    (30, "Karthus", "Karthus", "karthus"),
+    (31, "Chogath", "Cho'Gath", "chogath"),
+    (32, "Amumu", "Amumu", "amumu"),
+    (33, "Rammus", "Rammus", "rammus"),
+    (34, "Anivia", "Anivia", "anivia"),
+    (35, "Shaco", "Shaco", "shaco"),
+    (36, "DrMundo", "Dr. Mundo", "drmundo"),
+    (37, "Sona", "Sona", "sona"),
+    (38, "Kassadin", "Kassadin", "kassadin"),
+    (39, "Irelia", "Irelia", "irelia"),
+    (40, "Janna", "Janna", "janna"),
+    (41, "Gangplank", "Gangplank", "gangplank"),
+    (42, "Corki", "Corki", "corki"),
+    (43, "Karma", "Karma", "karma"),
+    (44, "Taric", "Taric", "taric"),
+    (45, "Veigar", "Veigar", "veigar"),
+    (48, "Trundle", "Trundle", "trundle"),
+    (50, "Swain", "Swain", "swain"),
+    (51, "Caitlyn", "Caitlyn", "caitlyn"),
+    (53, "Blitzcrank", "Blitzcrank", "blitzcrank"),
+    (54, "Malphite", "Malphite", "malphite"),
+    (55, "Katarina", "Katarina", "katarina"),
+    (56, "Nocturne", "Nocturne", "nocturne"),
+    (57, "Maokai", "Maokai", "maokai"),
+    (58, "Renekton", "Renekton", "renekton"),
+    (59, "JarvanIV", "Jarvan IV", "jarvaniv"),
+    (60, "Elise", "Elise", "elise"),
+    (61, "Orianna", "Orianna", "orianna"),
+    (62, "MonkeyKing", "Wukong", "wukong"),
+    (63, "Brand", "Brand", "brand"),
+    (64, "LeeSin", "Lee Sin", "leesin"),
+    (67, "Vayne", "Vayne", "vayne"),
+    (68, "Rumble", "Rumble", "rumble"),
+    (69, "Cassiopeia", "Cassiopeia", "cassiopeia"),
+    (72, "Skarner", "Skarner", "skarner"),
+    (74, "Heimerdinger", "Heimerdinger", "heimerdinger"),
+    (75, "Nasus", "Nasus", "nasus"),
+    (76, "Nidalee", "Nidalee", "nidalee"),
+    (77, "Udyr", "Udyr", "udyr"),
+    (78, "Poppy", "Poppy", "poppy"),
+    (79, "Gragas", "Gragas", "gragas"),
+    (80, "Pantheon", "Pantheon", "pantheon"),
+    (81, "Ezreal", "Ezreal", "ezreal"),
+    (82, "Mordekaiser", "Mordekaiser", "mordekaiser"),
+    (83, "Yorick", "Yorick", "yorick"),
+    (84, "Akali", "Akali", "akali"),
+    (85, "Kennen", "Kennen", "kennen"),
+    (86, "Garen", "Garen", "garen"),
+    (89, "Leona", "Leona", "leona"),
+    (90, "Malzahar", "Malzahar", "malzahar"),
+    (91, "Talon", "Talon", "talon"),
+    (92, "Riven", "Riven", "riven"),
+    (96, "KogMaw", "Kog'Maw", "kogmaw"),
+    (98, "Shen", "Shen", "shen"),
+    (99, "Lux", "Lux", "lux"),
+    (101, "Xerath", "Xerath", "xerath"),
+    (102, "Shyvana", "Shyvana", "shyvana"),
+    (103, "Ahri", "Ahri", "ahri"),
+    (104, "Graves", "Graves", "graves"),
+    (105, "Fizz", "Fizz", "fizz"),
+    (106, "Volibear", "Volibear", "volibear"),
+    (107, "Rengar", "Rengar", "rengar"),
+    (110, "Varus", "Varus", "varus"),
+    (111, "Nautilus", "Nautilus", "nautilus"),
+    (112, "Viktor", "Viktor", "viktor"),
+    (113, "Sejuani", "Sejuani", "sejuani"),
+    (114, "Fiora", "Fiora", "fiora"),
+    (115, "Ziggs", "Ziggs", "ziggs"),
+    (117, "Lulu", "Lulu", "lulu"),
+    (119, "Draven", "Draven", "draven"),
+    (120, "Hecarim", "Hecarim", "hecarim"),
+    (121, "Khazix", "Kha'Zix", "khazix"),
+    (122, "Darius", "Darius", "darius"),
+    (126, "Jayce", "Jayce", "jayce"),
+    (127, "Lissandra", "Lissandra", "lissandra"),
+    (131, "Diana", "Diana", "diana"),
+    (133, "Quinn", "Quinn", "quinn"),
+    (134, "Syndra", "Syndra", "syndra"),
+    (136, "AurelionSol", "Aurelion Sol", "aurelionsol"),
+    (141, "Kayn", "Kayn", "kayn"),
+    (142, "Zoe", "Zoe", "zoe"),
+    (143, "Zyra", "Zyra", "zyra"),
+    (145, "Kaisa", "Kai'Sa", "kaisa"),
+    (147, "Seraphine", "Seraphine", "seraphine"),
+    (150, "Gnar", "Gnar", "gnar"),
+    (154, "Zac", "Zac", "zac"),
+    (157, "Yasuo", "Yasuo", "yasuo"),
+    (161, "Velkoz", "Vel'Koz", "velkoz"),
+    (163, "Taliyah", "Taliyah", "taliyah"),
+    (164, "Camille", "Camille", "camille"),
+    (166, "Akshan", "Akshan", "akshan"),
+    (200, "Belveth", "Bel'Veth", "belveth"),
+    (201, "Braum", "Braum", "braum"),
+    (202, "Jhin", "Jhin", "jhin"),
+    (203, "Kindred", "Kindred", "kindred"),
+    (221, "Zeri", "Zeri", "zeri"),
+    (222, "Jinx", "Jinx", "jinx"),
+    (223, "TahmKench", "Tahm Kench", "tahmkench"),
+    (233, "Briar", "Briar", "briar"),
+    (234, "Viego", "Viego", "viego"),
+    (235, "Senna", "Senna", "senna"),
+    (236, "Lucian", "Lucian", "lucian"),
+    (238, "Zed", "Zed", "zed"),
+    (240, "Kled", "Kled", "kled"),
+    (245, "Ekko", "Ekko", "ekko"),
+    (246, "Qiyana", "Qiyana", "qiyana"),
+    (254, "Vi", "Vi", "vi"),
+    (266, "Aatrox", "Aatrox", "aatrox"),
+    (267, "Nami", "Nami", "nami"),
+    (268, "Azir", "Azir", "azir"),
+    (350, "Yuumi", "Yuumi", "yuumi"),
+    (360, "Samira", "Samira", "samira"),
+    (412, "Thresh", "Thresh", "thresh"),
+    (420, "Illaoi", "Illaoi", "illaoi"),
+    (421, "RekSai", "Rek'Sai", "reksai"),
+    (427, "Ivern", "Ivern", "ivern"),
+    (429, "Kalista", "Kalista", "kalista"),
+    (432, "Bard", "Bard", "bard"),
+    (497, "Rakan", "Rakan", "rakan"),
+    (498, "Xayah", "Xayah", "xayah"),
+    (516, "Ornn", "Ornn", "ornn"),
+    (517, "Sylas", "Sylas", "sylas"),
+    (518, "Neeko", "Neeko", "neeko"),
+    (523, "Aphelios", "Aphelios", "aphelios"),
+    (526, "Rell", "Rell", "rell"),
+    (555, "Pyke", "Pyke", "pyke"),
+    (711, "Vex", "Vex", "vex"),
+    (777, "Yone", "Yone", "yone"),
+    (799, "Ambessa", "Ambessa", "ambessa"),
+    (800, "Mel", "Mel", "mel"),
+    (875, "Sett", "Sett", "sett"),
+    (876, "Lillia", "Lillia", "lillia"),
+    (887, "Gwen", "Gwen", "gwen"),
+    (888, "Renata", "Renata Glasc", "renata"),
+    (893, "Aurora", "Aurora", "aurora"),
+    (895, "Nilah", "Nilah", "nilah"),
+    (897, "KSante", "K'Sante", "ksante"),
+    (901, "Smolder", "Smolder", "smolder"),
+    (902, "Milio", "Milio", "milio"),
+    (910, "Hwei", "Hwei", "hwei"),
+    (950, "Naafiri", "Naafiri", "naafiri"),
+];
+
+/// Nicknames players search by, as `(alias, lcu id)`.
+const ALIASES: &[(&str, i32)] = &[
+    ("tf", 4),
+    ("xin", 5),
+    ("lb", 7),
+    ("vlad", 8),
+    ("fiddle", 9),
+    ("yi", 11),
+    ("ww", 19),
+    ("mf", 21),
+    ("trynd", 23),
+    ("cho", 31),
+    ("mundo", 36),
+    ("kass", 38),
+    ("gp", 41),
+    ("blitz", 53),
+    ("kat", 55),
+    ("noc", 56),
+    ("j4", 59),
+    ("jarvan", 59),
+    ("ori", 61),
+    ("wu", 62),
+    ("monkey", 62),
+    ("lee", 64),
+    ("cass", 69),
+    ("heimer", 74),
+    ("donger", 74),
+    ("morde", 82),
+    ("kog", 96),
+    ("voli", 106),
+    ("naut", 111),
+    ("sej", 113),
+    ("kha", 121),
+    ("liss", 127),
+    ("asol", 136),
+    ("vel", 161),
+    ("tk", 223),
+    ("tahm", 223),
+    ("rek", 421),
+    ("naafi", 950),
+];
+
+/// A champion, kept as the lcu id and the ddragon id.
+///
+/// Champions released after `CHAMPIONS` was written still work, their
+/// provider slugs are derived from the ddragon id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ChampionId {
+    key: i32,
+    id: String,
+}
+
+impl ChampionId {
+    pub fn new(key: i32, id: &str) -> Self {
+        ChampionId {
+            key,
+            id: id.to_owned(),
+        }
+    }
+
+    /// Champion of `CHAMPIONS` by lcu id, ddragon id, english name or provider slug.
+    pub fn resolve(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let row = match s.parse::<i32>() {
+            Ok(key) => CHAMPIONS.iter().find(|row| row.0 == key),
+            Err(_) => {
+                let name = normalize(s);
+                CHAMPIONS.iter().find(|row| {
+                    row.1.eq_ignore_ascii_case(s) || normalize(row.2) == name || row.3 == name
+                })
+            }
+        }?;
+        Some(ChampionId::new(row.0, row.1))
+    }
+
+    /// English name, if the champion is known to `CHAMPIONS`.
+    pub fn english_name(&self) -> Option<&'static str> {
+        CHAMPIONS.iter().find(|row| row.0 == self.key).map(|row| row.2)
+    }
+
+    pub fn aliases(&self) -> Vec<&'static str> {
+        ALIASES
+            .iter()
+            .filter(|(_, key)| *key == self.key)
+            .map(|(alias, _)| *alias)
+            .collect()
+    }
+
+    /// Numeric id used by the lcu, also the `key` of ddragon.
+    pub fn key(&self) -> i32 {
+        self.key
+    }
+
+    /// Id of ddragon, like `MonkeyKing`.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Champion part of op.gg urls, like `wukong`.
+    pub fn opgg_slug(&self) -> String {
+        match CHAMPIONS.iter().find(|row| row.0 == self.key) {
+            Some(row) => row.3.to_owned(),
+            None => normalize(&self.id),
+        }
+    }
+}
+
+impl fmt::Display for ChampionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.id)
+    }
+}
+
+/// Lowercase alphanumeric form of a name, `Nunu & Willump` to `nunuwillump`.
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    const DDRAGON_CHAMPIONS: &str = include_str!("../tests/fixtures/ddragon/champion.json");
+
+    #[test]
+    fn ddragon_champions_resolve() {
+        let json: serde_json::Value = serde_json::from_str(DDRAGON_CHAMPIONS).unwrap();
+        let champions = json["data"].as_object().unwrap();
+        assert!(!champions.is_empty());
+        for champion in champions.values() {
+            let id = champion["id"].as_str().unwrap();
+            let key = champion["key"].as_str().unwrap();
+            let expected = ChampionId::new(key.parse().unwrap(), id);
+            assert_eq!(ChampionId::resolve(key), Some(expected.clone()), "{}", key);
+            assert_eq!(ChampionId::resolve(id), Some(expected.clone()), "{}", id);
+            let slug = CHAMPIONS
+                .iter()
+                .find(|row| row.1 == id)
+                .map(|row| row.3)
+                .unwrap_or_else(|| panic!("{} has no opgg slug", id));
+            assert_eq!(expected.opgg_slug(), slug);
+        }
+    }
+
+    #[test]
+    fn table_has_no_duplicates() {
+        let keys: HashSet<_> = CHAMPIONS.iter().map(|row| row.0).collect();
+        let ids: HashSet<_> = CHAMPIONS.iter().map(|row| row.1).collect();
+        let slugs: HashSet<_> = CHAMPIONS.iter().map(|row| row.3).collect();
+        assert_eq!(keys.len(), CHAMPIONS.len());
+        assert_eq!(ids.len(), CHAMPIONS.len());
+        assert_eq!(slugs.len(), CHAMPIONS.len());
+    }
+
+    #[test]
+    fn every_champion_resolves_from_each_form() {
+        for &(key, id, name, slug) in CHAMPIONS {
+            let expected = ChampionId::new(key, id);
+            assert_eq!(ChampionId::resolve(&key.to_string()), Some(expected.clone()));
+            assert_eq!(ChampionId::resolve(id), Some(expected.clone()), "{}", id);
+            assert_eq!(ChampionId::resolve(name), Some(expected.clone()), "{}", name);
+            assert_eq!(ChampionId::resolve(slug), Some(expected.clone()), "{}", slug);
+            assert_eq!(expected.opgg_slug(), slug);
+        }
+    }
+
+    #[test]
+    fn every_slug_is_url_safe() {
+        for &(_, _, _, slug) in CHAMPIONS {
+            assert!(
+                !slug.is_empty()
+                    && slug
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()),
+                "{}",
+                slug
+            );
+        }
+    }
+
+    #[test]
+    fn names_that_used_to_break() {
+        let wukong = ChampionId::resolve("Wukong").unwrap();
+        assert_eq!((wukong.key(), wukong.id()), (62, "MonkeyKing"));
+        assert_eq!(wukong.opgg_slug(), "wukong");
+        assert_eq!(ChampionId::resolve("Nunu & Willump").unwrap().opgg_slug(), "nunu");
+        assert_eq!(ChampionId::resolve("Renata Glasc").unwrap().opgg_slug(), "renata");
+        assert_eq!(ChampionId::resolve("Cho'Gath").unwrap().id(), "Chogath");
+        assert_eq!(ChampionId::resolve("dr. mundo").unwrap().id(), "DrMundo");
+        assert_eq!(ChampionId::resolve("monkeyking").unwrap().key(), 62);
+    }
+
+    #[test]
+    fn every_alias_is_a_known_champion() {
+        for &(alias, key) in ALIASES {
+            assert!(CHAMPIONS.iter().any(|row| row.0 == key), "{}", alias);
+            assert_eq!(normalize(alias), alias);
+        }
+    }
+
+    #[test]
+    fn unknown_champion_derives_slug() {
+        assert_eq!(ChampionId::resolve("Nobody"), None);
+        assert_eq!(ChampionId::new(9999, "New'Champ").opgg_slug(), "newchamp");
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Serialize;
+
+use crate::champion_id::normalize;
+
+/// A champion and every name it can be searched by.
+pub struct Candidate {
+    pub key: i32,
+    pub id: String,
+    /// Shown name, in the current language.
+    pub name: String,
+    /// Names of all loaded languages, the english one and the ddragon id.
+    pub names: Vec<String>,
+    pub aliases: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChampionMatch {
+    pub key: i32,
+    pub id: String,
+    pub name: String,
+    /// Higher is more relevant.
+    pub score: u32,
+}
+
+/// Champions matching `query` by exact name or alias, prefix, substring, or
+/// with a few typos, the most relevant first.
+pub fn search(candidates: Vec<Candidate>, query: &str, limit: usize) -> Vec<ChampionMatch> {
+    let query = normalize(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut matches: Vec<ChampionMatch> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let by_alias = candidate
+                .aliases
+                .iter()
+                .filter(|alias| **alias == query)
+                .map(|_| 95);
+            let by_name = candidate.names.iter().filter_map(|name| score(&normalize(name), &query));
+            let score = by_alias.chain(by_name).max()?;
+            Some(ChampionMatch {
+                key: candidate.key,
+                id: candidate.id,
+                name: candidate.name,
+                score,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    matches.truncate(limit);
+    matches
+}
+
+/// Relevance of a normalized name for a normalized query, `None` if it doesn't match.
+fn score(name: &str, query: &str) -> Option<u32> {
+    if name.is_empty() {
+        return None;
+    }
+    if name == query {
+        return Some(100);
+    }
+    if name.starts_with(query) {
+        // Shorter names first, so "ka" gives Kai'Sa before Kassadin.
+        return Some(80 - (name.len() - query.len()).min(10) as u32);
+    }
+    if name.contains(query) {
+        return Some(60);
+    }
+    let allowed = match query.chars().count() {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    };
+    // Typos are compared to the start of the name, so half typed names match too.
+    let head: String = name.chars().take(query.chars().count()).collect();
+    let distance = edit_distance(&head, query).min(edit_distance(name, query));
+    (distance <= allowed).then(|| 40 - 10 * distance as u32)
+}
+
+/// Levenshtein distance, with swapped neighbours counted as one edit.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::champion_id::ChampionId;
+
+    /// Every known champion, searched by its english name and ddragon id.
+    fn champions() -> Vec<Candidate> {
+        (1..1000)
+            .filter_map(|key| ChampionId::resolve(&key.to_string()))
+            .map(|champion| Candidate {
+                key: champion.key(),
+                id: champion.id().to_string(),
+                name: champion.english_name().unwrap().to_string(),
+                names: vec![
+                    champion.english_name().unwrap().to_string(),
+                    champion.id().to_string(),
+                ],
+                aliases: champion.aliases(),
+            })
+            .collect()
+    }
+
+    fn candidate(key: i32, name: &str) -> Candidate {
+        Candidate {
+            key,
+            id: name.to_string(),
+            name: name.to_string(),
+            names: vec![name.to_string()],
+            aliases: Vec::new(),
+        }
+    }
+
+    fn first(query: &str) -> String {
+        search(champions(), query, 5)
+            .first()
+            .map(|m| m.id.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn aliases() {
+        assert_eq!(first("mf"), "MissFortune");
+        assert_eq!(first("tf"), "TwistedFate");
+        assert_eq!(first("j4"), "JarvanIV");
+        assert_eq!(first("asol"), "AurelionSol");
+    }
+
+    #[test]
+    fn typos() {
+        assert_eq!(first("kasadin"), "Kassadin");
+        assert_eq!(first("ahir"), "Ahri");
+    }
+
+    #[test]
+    fn exact_before_prefix_before_substring_before_typo() {
+        let candidates = vec![
+            candidate(1, "Ahxi"),
+            candidate(2, "Mahri"),
+            candidate(3, "Ahrimanes"),
+            candidate(4, "Ahri"),
+        ];
+        let names: Vec<String> = search(candidates, "ahri", 10)
+            .into_iter()
+            .map(|m| m.name)
+            .collect();
+        assert_eq!(names, vec!["Ahri", "Ahrimanes", "Mahri", "Ahxi"]);
+    }
+
+    #[test]
+    fn empty_query_matches_nothing() {
+        assert!(search(champions(), "", 5).is_empty());
+        assert!(search(champions(), " '.", 5).is_empty());
+    }
+}
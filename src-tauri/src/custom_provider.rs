@@ -1,122 +1,843 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
+use chrono::{DateTime, Utc};
 use directories::ProjectDirs;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use tokio::{
-    fs::{self, File},
-    io::AsyncReadExt,
+    fs,
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        Mutex, OwnedMutexGuard,
+    },
 };
-use tracing::error;
+use tracing::{error, info};
+
+pub use share::{Conflict, ImportConflict, ImportReport, ImportSource, SharedBuild};
+pub use templates::{RuneTemplate, TemplateOverride, TemplateStore};
 
 use crate::{
-    source_provider::{GameMode, LolRuneItem, RuneItem},
-    util::write_to_file,
+    source_provider::{GameMode, ItemBlock, LolRuneItem, RuneItem, Skill, SpellItem},
+    util::write_atomic,
 };
 
+pub mod share;
+pub mod templates;
+
+/// Version of `CustomBuild` files, bump it and add a step to `parse_build` on changes.
+pub const CUSTOM_BUILD_VERSION: u32 = 2;
+
 #[derive(Debug)]
 pub struct CustomProvider {
-    custom_builds: PathBuf,
+    custom_builds: RwLock<PathBuf>,
+    /// One lock per build file, held while a build is read, changed and written.
+    locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    /// Parsed builds by file name, refreshed when a file is changed by others.
+    index: RwLock<HashMap<String, CustomBuild>>,
+    /// `None` until read, or after the file changed on disk.
+    templates: RwLock<Option<TemplateStore>>,
+    watcher: std::sync::Mutex<Option<RecommendedWatcher>>,
+    file_events: UnboundedSender<PathBuf>,
+    file_event_receiver: Mutex<UnboundedReceiver<PathBuf>>,
+}
+
+/// A custom build changed on disk by something else than Lola.
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomBuildChange {
+    pub champion_id: String,
+    pub game_mode: String,
+    pub removed: bool,
+}
+
+/// A saved rune page, with what it is meant for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomRunePage {
+    /// Stays the same on edits, unique in its build.
+    pub id: u32,
+    pub page: LolRuneItem,
+    /// Lanes the page is meant for, like `mid`.
+    #[serde(default)]
+    pub lanes: Vec<String>,
+    /// Ddragon ids of the champions the page is meant against.
+    #[serde(default)]
+    pub matchups: Vec<String>,
+    #[serde(default)]
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// What a player saved for a champion and mode, one file each.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomBuild {
+    pub version: u32,
+    /// In the order they are shown.
+    #[serde(default)]
+    pub pages: Vec<CustomRunePage>,
+    /// Id of the next added page, ids of removed pages are not reused.
+    #[serde(default)]
+    pub next_page_id: u32,
+    /// Pairs of summoner spell ids.
+    #[serde(default)]
+    pub spells: Vec<Vec<i32>>,
+    #[serde(default)]
+    pub items: Vec<ItemBlock>,
+    #[serde(default)]
+    pub skill_order: Vec<Skill>,
+    /// Lanes the build is meant for, like `mid`.
+    #[serde(default)]
+    pub lanes: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl Default for CustomBuild {
+    fn default() -> Self {
+        CustomBuild {
+            version: CUSTOM_BUILD_VERSION,
+            pages: Vec::new(),
+            next_page_id: 0,
+            spells: Vec::new(),
+            items: Vec::new(),
+            skill_order: Vec::new(),
+            lanes: Vec::new(),
+            notes: String::new(),
+        }
+    }
+}
+
+impl CustomBuild {
+    /// Add `page` at the end, returns its id.
+    pub fn add_page(&mut self, page: LolRuneItem) -> u32 {
+        let id = self.next_page_id.max(self.pages.iter().map(|p| p.id + 1).max().unwrap_or(0));
+        self.next_page_id = id + 1;
+        self.pages.push(CustomRunePage {
+            id,
+            page,
+            lanes: Vec::new(),
+            matchups: Vec::new(),
+            last_used: None,
+        });
+        id
+    }
+
+    pub fn page_mut(&mut self, id: u32) -> Result<&mut CustomRunePage, String> {
+        self.pages
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("No custom rune page {}", id))
+    }
+
+    /// Whether a page other than `except` is named `name`.
+    pub fn has_page_named(&self, name: &str, except: Option<u32>) -> bool {
+        self.pages
+            .iter()
+            .any(|p| p.page.name == name && Some(p.id) != except)
+    }
+
+    pub fn spell_items(&self) -> Vec<SpellItem> {
+        self.spells
+            .iter()
+            .map(|ids| SpellItem {
+                ids: ids.clone(),
+                win: 0,
+                play: 0,
+                pick_rate: 0.0,
+            })
+            .collect()
+    }
 }
 
 impl CustomProvider {
-    pub fn initialize() -> Self {
-        if let Some(proj) = ProjectDirs::from("com", "Jinte", "Lola") {
-            let custom_builds = proj.data_dir().join("custom_builds");
-            std::fs::create_dir_all(&custom_builds).unwrap();
-            return CustomProvider { custom_builds };
+    /// Builds are kept in `custom_builds`, or in the default directory if it can't be made,
+    /// like on a drive that is not mounted.
+    pub fn initialize(custom_builds: PathBuf) -> Result<Self, String> {
+        let custom_builds = match std::fs::create_dir_all(&custom_builds) {
+            Ok(_) => custom_builds,
+            Err(err) => {
+                error!(
+                    "Can't use custom builds directory {:?}, use the default one: {}",
+                    custom_builds, err
+                );
+                let dir = default_dir();
+                std::fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+                dir
+            }
+        };
+        tauri::async_runtime::block_on(CustomProvider::migrate_rune_files(&custom_builds));
+        let (file_events, file_event_receiver) = mpsc::unbounded_channel();
+        let custom = CustomProvider {
+            custom_builds: RwLock::new(custom_builds),
+            locks: Mutex::new(HashMap::new()),
+            index: RwLock::new(HashMap::new()),
+            templates: RwLock::new(None),
+            watcher: std::sync::Mutex::new(None),
+            file_events,
+            file_event_receiver: Mutex::new(file_event_receiver),
+        };
+        match custom.watch(&custom.dir()) {
+            Ok(watcher) => *custom.watcher.lock().unwrap() = Some(watcher),
+            Err(err) => error!("Watch custom builds failed: {}", err),
         }
-        panic!("Can't initialize!")
+        Ok(custom)
     }
 
-    pub async fn get_champion_lol_runes(
-        &self,
-        champion_name: &str,
-        mode: GameMode,
-    ) -> Vec<LolRuneItem> {
-        let build_file = self
-            .custom_builds
-            .join(&CustomProvider::get_file_name(champion_name, mode));
-        if build_file.is_file() {
-            let file = File::open(build_file).await;
-            match file {
-                Ok(mut file) => {
-                    let mut data = String::new();
-                    file.read_to_string(&mut data)
-                        .await
-                        .map_err(|err| err.to_string())
-                        .unwrap();
-                    return serde_json::from_str(&data).unwrap();
+    fn dir(&self) -> PathBuf {
+        self.custom_builds.read().unwrap().clone()
+    }
+
+    /// Keep builds in `dir` from now on, the ones in the old directory stay there.
+    /// Returns whether the directory changed.
+    pub async fn set_dir(&self, dir: PathBuf) -> Result<bool, String> {
+        if self.dir() == dir {
+            return Ok(false);
+        }
+        fs::create_dir_all(&dir).await.map_err(|err| err.to_string())?;
+        // Before switching, so a directory that can't be watched is not taken.
+        let watcher = self.watch(&dir)?;
+        CustomProvider::migrate_rune_files(&dir).await;
+        *self.custom_builds.write().unwrap() = dir;
+        self.index.write().unwrap().clear();
+        *self.templates.write().unwrap() = None;
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(true)
+    }
+
+    /// Watcher of `dir` for files changed by others, it replaces the last one once stored.
+    fn watch(&self, dir: &Path) -> Result<RecommendedWatcher, String> {
+        let events = self.file_events.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            match event {
+                Ok(event) if !event.kind.is_access() => {
+                    for path in event.paths {
+                        let _ = events.send(path);
+                    }
                 }
+                Ok(_) => {}
+                Err(err) => error!("{:?}", err),
+            }
+        })
+        .map_err(|err| err.to_string())?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|err| err.to_string())?;
+        Ok(watcher)
+    }
+
+    /// Wait for build files to change on disk and re-index them. Only builds that
+    /// differ from the index are returned, so writes of Lola itself are left out.
+    /// `None` once no more changes can come.
+    pub async fn next_changes(&self) -> Option<Vec<CustomBuildChange>> {
+        let mut paths = Vec::new();
+        {
+            let mut events = self.file_event_receiver.lock().await;
+            paths.push(events.recv().await?);
+            // Editors and sync tools write a file in a few steps.
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            while let Ok(path) = events.try_recv() {
+                paths.push(path);
+            }
+        }
+        let mut names: Vec<String> = paths
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".json"))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let mut changes = Vec::new();
+        for name in names {
+            if name == templates::TEMPLATES_FILE {
+                changes.extend(self.reload_templates().await);
+                continue;
+            }
+            let (champion, mode) = match parse_file_name(&name) {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let _guard = self.lock(&name).await;
+            let build = match self.read_file(&name).await {
+                Ok(build) => build,
                 Err(err) => {
-                    error!("{:?}", err);
-                    return Vec::new();
+                    // Reads show the error until the file is fixed.
+                    error!("{}", err);
+                    None
+                }
+            };
+            let old = {
+                let mut index = self.index.write().unwrap();
+                match &build {
+                    Some(build) => index.insert(name.clone(), build.clone()),
+                    None => index.remove(&name),
                 }
+            };
+            if old != build {
+                info!("Custom build {} changed on disk", name);
+                changes.push(CustomBuildChange {
+                    champion_id: champion.to_string(),
+                    game_mode: mode.to_string(),
+                    removed: !self.dir().join(&name).is_file(),
+                });
+            }
+        }
+        Some(changes)
+    }
+
+    /// Turn `*-runes.json` files, which only had rune pages, into custom builds.
+    async fn migrate_rune_files(dir: &Path) {
+        let mut entries = match fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(err) => return error!("{:?}", err),
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let old = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stem = match name.strip_suffix("-runes.json") {
+                Some(stem) => stem,
+                None => continue,
+            };
+            let new = dir.join(format!("{}.json", stem));
+            if new.exists() {
+                continue;
+            }
+            match CustomProvider::migrate_rune_file(&old, &new).await {
+                Ok(_) => info!("Migrated {} to a custom build.", name),
+                Err(err) => error!("Migrate {} failed: {}", name, err),
+            }
+        }
+    }
+
+    async fn migrate_rune_file(old: &Path, new: &Path) -> Result<(), String> {
+        let json = fs::read_to_string(old)
+            .await
+            .map_err(|err| err.to_string())?;
+        let build = parse_build(&json)?;
+        let json = serde_json::to_string(&build).map_err(|err| err.to_string())?;
+        write_atomic(new, json.as_bytes(), false).await?;
+        // Only once the build is in place, so a failed write keeps the pages.
+        fs::remove_file(old).await.map_err(|err| err.to_string())
+    }
+
+    /// Saved build, `None` if there is none.
+    async fn read_build(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+    ) -> Result<Option<CustomBuild>, String> {
+        let file_name = CustomProvider::get_file_name(champion_name, mode);
+        if let Some(build) = self.index.read().unwrap().get(&file_name) {
+            return Ok(Some(build.clone()));
+        }
+        let build = self.read_file(&file_name).await?;
+        if let Some(build) = &build {
+            self.index.write().unwrap().insert(file_name, build.clone());
+        }
+        Ok(build)
+    }
+
+    /// Build in the file, skipping the index.
+    async fn read_file(&self, file_name: &str) -> Result<Option<CustomBuild>, String> {
+        let build_file = self.dir().join(file_name);
+        if !build_file.is_file() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&build_file)
+            .await
+            .map_err(|err| err.to_string())?;
+        parse_build(&json)
+            .map(Some)
+            .map_err(|err| format!("Broken custom build {:?}: {}", build_file, err))
+    }
+
+    /// Saved build, an empty one if there is none.
+    pub async fn get_build(&self, champion_name: &str, mode: GameMode) -> Result<CustomBuild, String> {
+        Ok(self.read_build(champion_name, mode).await?.unwrap_or_default())
+    }
+
+    async fn lock(&self, file_name: &str) -> OwnedMutexGuard<()> {
+        let lock = self
+            .locks
+            .lock()
+            .await
+            .entry(file_name.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+
+    /// Write without taking the lock, the previous version is kept as a backup.
+    async fn write_build(&self, file_name: &str, build: &CustomBuild) -> Result<(), String> {
+        let build = CustomBuild {
+            version: CUSTOM_BUILD_VERSION,
+            ..build.clone()
+        };
+        let json = serde_json::to_string(&build).map_err(|err| err.to_string())?;
+        write_atomic(&self.dir().join(file_name), json.as_bytes(), true).await?;
+        self.index.write().unwrap().insert(file_name.to_string(), build);
+        Ok(())
+    }
+
+    pub async fn save_build(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        build: &CustomBuild,
+    ) -> Result<(), String> {
+        self.update_pages(champion_name, mode, |current, templates| {
+            if let Some(page) = build.pages.iter().find(|p| templates.contains(&p.page.name)) {
+                return Err(format!("Rune template {} is linked already", page.page.name));
+            }
+            *current = build.clone();
+            Ok(())
+        })
+        .await
+    }
+
+    /// Read, change and save a build, nothing is saved if `change` fails. A broken
+    /// file is kept as is rather than replaced.
+    async fn update_build<T>(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        change: impl FnOnce(&mut CustomBuild) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let file_name = CustomProvider::get_file_name(champion_name, mode);
+        let _guard = self.lock(&file_name).await;
+        let mut build = self.read_build(champion_name, mode).await?.unwrap_or_default();
+        let result = change(&mut build)?;
+        self.write_build(&file_name, &build).await?;
+        Ok(result)
+    }
+
+    /// `update_build` for changes that name pages, `change` also gets the names of the
+    /// linked templates, which custom pages can't take.
+    async fn update_pages<T>(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        change: impl FnOnce(&mut CustomBuild, &[String]) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let file_name = CustomProvider::get_file_name(champion_name, mode);
+        // Linking takes it too, so no template gets linked under the name meanwhile.
+        let _guard = self.lock(&file_name).await;
+        let templates = self.template_names(champion_name, mode).await?;
+        let mut build = self.read_build(champion_name, mode).await?.unwrap_or_default();
+        let result = change(&mut build, &templates)?;
+        self.write_build(&file_name, &build).await?;
+        Ok(result)
+    }
+
+    /// Names of the templates linked to a build.
+    async fn template_names(&self, champion_name: &str, mode: GameMode) -> Result<Vec<String>, String> {
+        Ok(self
+            .template_pages(champion_name, mode)
+            .await?
+            .into_iter()
+            .map(|(_, page)| page.name)
+            .collect())
+    }
+
+    /// Rune pages in their order, then the linked templates. The id of a rune is the
+    /// id of its custom page, or of its template with `template_id` set.
+    pub async fn get_champion_runes(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+    ) -> Result<Vec<RuneItem>, String> {
+        let pages = self.get_build(champion_name, mode).await?.pages;
+        let templates = self.template_pages(champion_name, mode).await?;
+        let mut runes = Vec::with_capacity(pages.len() + templates.len());
+
+        let pages = pages.into_iter().map(|p| (p.id, None, p.page));
+        let templates = templates.into_iter().map(|(id, page)| (id, Some(id), page));
+        for (id, template_id, page) in pages.chain(templates) {
+            match RuneItem::try_from(page) {
+                Ok(rune) => runes.push(RuneItem {
+                    id: id as i64,
+                    template_id,
+                    ..rune
+                }),
+                Err(err) => error!("Skip custom rune: {}", err),
             }
-        } else {
-            return Vec::new();
         }
+
+        Ok(runes)
     }
 
-    pub async fn get_champion_runes(&self, champion_name: &str, mode: GameMode) -> Vec<RuneItem> {
-        let lol = self.get_champion_lol_runes(champion_name, mode).await;
-        let mut runes = Vec::with_capacity(lol.len());
+    /// Replace the runes of a page, its name, tags and place are kept unless `page` renames it.
+    pub async fn update_page(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        id: u32,
+        page: LolRuneItem,
+    ) -> Result<(), String> {
+        self.update_pages(champion_name, mode, |build, templates| {
+            if build.has_page_named(&page.name, Some(id)) {
+                return Err(format!("Custom rune page {} exists already", page.name));
+            }
+            if templates.contains(&page.name) {
+                return Err(format!("Rune template {} is linked already", page.name));
+            }
+            build.page_mut(id)?.page = page;
+            Ok(())
+        })
+        .await
+    }
 
-        for l in lol {
-            runes.push(RuneItem::from(l))
+    pub async fn rename_page(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        id: u32,
+        name: &str,
+    ) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(String::from("A rune page needs a name"));
         }
+        self.update_pages(champion_name, mode, |build, templates| {
+            if build.has_page_named(name, Some(id)) {
+                return Err(format!("Custom rune page {} exists already", name));
+            }
+            if templates.iter().any(|t| t == name) {
+                return Err(format!("Rune template {} is linked already", name));
+            }
+            build.page_mut(id)?.page.name = name.to_string();
+            Ok(())
+        })
+        .await
+    }
 
-        return runes;
+    /// Put pages in the order of `ids`, which must have every page once.
+    pub async fn reorder_pages(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        ids: &[u32],
+    ) -> Result<(), String> {
+        self.update_build(champion_name, mode, |build| {
+            let mut sorted = ids.to_vec();
+            sorted.sort_unstable();
+            sorted.dedup();
+            let mut existing: Vec<u32> = build.pages.iter().map(|p| p.id).collect();
+            existing.sort_unstable();
+            if sorted.len() != ids.len() || sorted != existing {
+                return Err(String::from("The order must have every custom rune page once"));
+            }
+            build
+                .pages
+                .sort_by_key(|p| ids.iter().position(|id| *id == p.id));
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn tag_page(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        id: u32,
+        lanes: Vec<String>,
+        matchups: Vec<String>,
+    ) -> Result<(), String> {
+        self.update_build(champion_name, mode, |build| {
+            let page = build.page_mut(id)?;
+            page.lanes = lanes;
+            page.matchups = matchups;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Remember the page was applied now.
+    pub async fn touch_page(&self, champion_name: &str, mode: GameMode, id: u32) -> Result<(), String> {
+        self.update_build(champion_name, mode, |build| {
+            build.page_mut(id)?.last_used = Some(Utc::now());
+            Ok(())
+        })
+        .await
     }
 
+    pub async fn remove_page(&self, champion_name: &str, mode: GameMode, id: u32) -> Result<(), String> {
+        self.update_build(champion_name, mode, |build| {
+            let i = build
+                .pages
+                .iter()
+                .position(|p| p.id == id)
+                .ok_or_else(|| format!("No custom rune page {}", id))?;
+            build.pages.remove(i);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Add a page at the end, returns its id.
     pub async fn add_champion_rune(
         &self,
         champion_name: &str,
         mode: GameMode,
         rune_item: LolRuneItem,
-    ) -> bool {
-        let mut runes = self.get_champion_lol_runes(champion_name, mode).await;
-        for r in &runes {
-            if r.name == rune_item.name {
-                return false;
+    ) -> Result<u32, String> {
+        self.update_pages(champion_name, mode, |build, templates| {
+            if build.has_page_named(&rune_item.name, None) {
+                return Err(format!("Custom rune page {} exists already", rune_item.name));
             }
-        }
-        runes.push(rune_item);
-        let build_file = self
-            .custom_builds
-            .join(&CustomProvider::get_file_name(champion_name, mode));
-        write_to_file(&build_file, &serde_json::to_string(&runes).unwrap())
+            if templates.contains(&rune_item.name) {
+                return Err(format!("Rune template {} is linked already", rune_item.name));
+            }
+            Ok(build.add_page(rune_item))
+        })
+        .await
+    }
+
+    pub async fn remove_champion_rune(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+        rune_name: &str,
+    ) -> Result<(), String> {
+        self.update_build(champion_name, mode, |build| {
+            let i = build
+                .pages
+                .iter()
+                .position(|p| p.page.name == rune_name)
+                .ok_or_else(|| format!("No custom rune page {}", rune_name))?;
+            build.pages.remove(i);
+            Ok(())
+        })
+        .await
+    }
+
+    /// Remove all rune pages, the rest of the build is kept.
+    pub async fn remove_champion_runes(&self, champion_name: &str, mode: GameMode) -> Result<(), String> {
+        self.update_build(champion_name, mode, |build| {
+            build.pages.clear();
+            Ok(())
+        })
+        .await
+    }
+
+    pub fn get_file_name(champion_name: &str, mode: GameMode) -> String {
+        format!("{}-{:?}.json", champion_name, mode)
+    }
+
+    /// Every saved build, broken files are skipped.
+    pub async fn list_builds(&self) -> Result<Vec<SharedBuild>, String> {
+        let mut dir = fs::read_dir(self.dir())
             .await
-            .unwrap();
-        return true;
-    }
-
-    pub async fn remove_champion_rune(&self, champion_name: &str, mode: GameMode, rune_name: &str) -> bool {
-        let mut runes = self.get_champion_lol_runes(champion_name, mode).await;
-        for (i, rune) in runes.iter().enumerate() {
-            if rune.name == rune_name {
-                runes.remove(i);
-                let build_file = self
-                    .custom_builds
-                    .join(&CustomProvider::get_file_name(champion_name, mode));
-                write_to_file(&build_file, &serde_json::to_string(&runes).unwrap())
-                    .await
-                    .unwrap();
-                return true;
+            .map_err(|err| err.to_string())?;
+        let mut builds = Vec::new();
+        while let Ok(Some(entry)) = dir.next_entry().await {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let (champion, mode) = match parse_file_name(&name) {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let parsed = fs::read_to_string(entry.path())
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|json| parse_build(&json));
+            match parsed {
+                Ok(build) => builds.push(SharedBuild {
+                    champion: champion.to_string(),
+                    mode: mode.to_string(),
+                    build,
+                }),
+                Err(err) => error!("Skip custom build {}: {}", name, err),
             }
         }
-        return false;
+        builds.sort_by(|a, b| (&a.champion, &a.mode).cmp(&(&b.champion, &b.mode)));
+        Ok(builds)
     }
 
-    pub async fn remove_champion_runes(&self, champion_name: &str, mode: GameMode) -> bool {
-        let build_file = self
-            .custom_builds
-            .join(&CustomProvider::get_file_name(champion_name, mode));
-        if build_file.is_file() {
-            fs::remove_file(build_file).await.unwrap();
+    pub async fn export_code(&self, champion_name: &str, mode: GameMode) -> Result<String, String> {
+        let build = self
+            .read_build(champion_name, mode)
+            .await?
+            .ok_or_else(|| format!("No custom build of {} in {:?}", champion_name, mode))?;
+        share::encode_code(&SharedBuild {
+            champion: champion_name.to_string(),
+            mode: mode.to_string(),
+            build,
+        })
+    }
+
+    pub async fn export_archive(&self, path: &Path) -> Result<usize, String> {
+        let builds = self.list_builds().await?;
+        let bytes = share::encode_archive(&builds)?;
+        write_atomic(path, &bytes, false).await?;
+        Ok(builds.len())
+    }
+
+    /// Rune page names of `incoming` that are taken, by file of the build.
+    pub async fn import_conflicts(
+        &self,
+        incoming: &[(GameMode, SharedBuild)],
+    ) -> Result<Vec<ImportConflict>, String> {
+        let mut conflicts = Vec::new();
+        for (mode, shared) in incoming {
+            let build = self.get_build(&shared.champion, *mode).await?;
+            let templates = self.template_names(&shared.champion, *mode).await?;
+            let names = share::conflicts(&build, &templates, &shared.build);
+            if !names.is_empty() {
+                conflicts.push(ImportConflict {
+                    file: CustomProvider::get_file_name(&shared.champion, *mode),
+                    names,
+                });
+            }
         }
-        return true;
+        Ok(conflicts)
     }
 
-    pub fn get_file_name(champion_name: &str, mode: GameMode) -> String {
-        format!("{}-{:?}-runes.json", champion_name, mode)
+    /// Merge `incoming` into the saved builds, `report` has what was already rejected.
+    pub async fn import(
+        &self,
+        incoming: Vec<(GameMode, SharedBuild)>,
+        conflict: Conflict,
+        mut report: ImportReport,
+    ) -> ImportReport {
+        for (mode, shared) in incoming {
+            let champion = shared.champion;
+            let merged = self
+                .update_pages(&champion, mode, |build, templates| {
+                    share::merge(build, templates, shared.build, conflict, &mut report);
+                    Ok(())
+                })
+                .await;
+            if let Err(err) = merged {
+                report.rejected.push(format!("{}: {}", champion, err));
+            }
+        }
+        report
+    }
+}
+
+/// Where builds are kept unless the config says otherwise.
+pub fn default_dir() -> PathBuf {
+    ProjectDirs::from("com", "Jinte", "Lola")
+        .unwrap()
+        .data_dir()
+        .join("custom_builds")
+}
+
+/// Champion and mode of a build file name, the reverse of `get_file_name`. Other
+/// files, like `Ahri-notes.json`, are not builds.
+fn parse_file_name(name: &str) -> Option<(&str, GameMode)> {
+    let (champion, mode) = name.strip_suffix(".json")?.rsplit_once('-')?;
+    let mode = [GameMode::Classic, GameMode::Aram, GameMode::Urf]
+        .into_iter()
+        .find(|m| format!("{:?}", m) == mode)?;
+    Some((champion, mode))
+}
+
+/// Read a custom build of any version, older ones are migrated.
+fn parse_build(json: &str) -> Result<CustomBuild, String> {
+    build_from_value(serde_json::from_str(json).map_err(|err| err.to_string())?)
+}
+
+fn build_from_value(value: Value) -> Result<CustomBuild, String> {
+    // Before version 1 a file was just the rune pages.
+    let mut value = match value {
+        Value::Array(runes) => json!({ "version": 1, "runes": runes }),
+        value => value,
+    };
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| String::from("No version"))?;
+    if version > CUSTOM_BUILD_VERSION as u64 {
+        return Err(format!(
+            "Version {} is made by a newer Lola, {} is supported.",
+            version, CUSTOM_BUILD_VERSION
+        ));
+    }
+    // Version 1 had plain client pages in `runes`.
+    let runes = match value.get_mut("runes") {
+        Some(runes) if version < 2 => runes.take(),
+        _ => Value::Null,
+    };
+    let mut build: CustomBuild = serde_json::from_value(value).map_err(|err| err.to_string())?;
+    if !runes.is_null() {
+        let runes: Vec<LolRuneItem> =
+            serde_json::from_value(runes).map_err(|err| err.to_string())?;
+        for rune in runes {
+            build.add_page(rune);
+        }
+    }
+    build.version = CUSTOM_BUILD_VERSION;
+    Ok(build)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNES_ARRAY: &str = include_str!("../tests/fixtures/custom_builds/runes-array.json");
+    const V1: &str = include_str!("../tests/fixtures/custom_builds/v1.json");
+    const V2: &str = include_str!("../tests/fixtures/custom_builds/v2.json");
+
+    fn names(build: &CustomBuild) -> Vec<(u32, &str)> {
+        build
+            .pages
+            .iter()
+            .map(|p| (p.id, p.page.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn runes_array() {
+        let build = parse_build(RUNES_ARRAY).unwrap();
+        assert_eq!(build.version, CUSTOM_BUILD_VERSION);
+        assert_eq!(names(&build), vec![(0, "Conqueror"), (1, "Electrocute")]);
+        assert_eq!(build.next_page_id, 2);
+        assert!(build.spells.is_empty() && build.notes.is_empty());
+    }
+
+    #[test]
+    fn version_1() {
+        let build = parse_build(V1).unwrap();
+        assert_eq!(build.version, CUSTOM_BUILD_VERSION);
+        assert_eq!(names(&build), vec![(0, "Conqueror"), (1, "Electrocute")]);
+        assert_eq!(build.pages[0].page.primary_style_id, 8000);
+        assert_eq!(build.spells, vec![vec![4, 14]]);
+        assert_eq!(build.items[0].items, vec![1055, 2003]);
+        assert_eq!(build.skill_order, vec![Skill::Q, Skill::E, Skill::W]);
+        assert_eq!(build.lanes, vec!["mid"]);
+        assert_eq!(build.notes, "Poke first");
+    }
+
+    #[test]
+    fn version_2() {
+        let build = parse_build(V2).unwrap();
+        assert_eq!(names(&build), vec![(3, "Conqueror"), (5, "Electrocute")]);
+        assert_eq!(build.next_page_id, 6);
+        assert_eq!(build.pages[0].lanes, vec!["top"]);
+        assert_eq!(build.pages[0].matchups, vec!["Darius"]);
+        assert_eq!(build.spells, vec![vec![4, 12]]);
+        assert_eq!(build.notes, "Trade short");
+    }
+
+    #[test]
+    fn only_build_file_names_are_parsed() {
+        let name = CustomProvider::get_file_name("MonkeyKing", GameMode::Aram);
+        assert_eq!(parse_file_name(&name), Some(("MonkeyKing", GameMode::Aram)));
+        assert_eq!(parse_file_name("Ahri-Urf.json"), Some(("Ahri", GameMode::Urf)));
+        assert_eq!(parse_file_name("Ahri-notes.json"), None);
+        assert_eq!(parse_file_name("Ahri-ARAM.json"), None);
+        assert_eq!(parse_file_name("templates.json"), None);
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let newer = json!({ "version": CUSTOM_BUILD_VERSION + 1, "pages": [] });
+        assert!(build_from_value(newer).unwrap_err().contains("newer Lola"));
+        assert!(build_from_value(json!({ "pages": [] })).is_err());
     }
 }
@@ -0,0 +1,462 @@
+use std::io::{Read, Write};
+
+use flate2::{
+    read::DeflateDecoder, read::GzDecoder, write::DeflateEncoder, write::GzEncoder, Compression,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{build_from_value, CustomBuild, CustomRunePage};
+
+/// Version of share codes and archives, it is the number after `lola` in a code.
+pub const SHARE_VERSION: u32 = 1;
+const CODE_PREFIX: &str = "lola";
+/// Most json a code or archive may inflate to, far more than any real set of builds.
+const MAX_JSON_SIZE: u64 = 16 * 1024 * 1024;
+
+/// A custom build with the champion and mode it is for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SharedBuild {
+    /// Ddragon id, like `MonkeyKing`.
+    pub champion: String,
+    pub mode: String,
+    pub build: CustomBuild,
+}
+
+/// `SharedBuild` as read, the build may be of an older version.
+#[derive(Debug, Deserialize)]
+struct RawSharedBuild {
+    champion: String,
+    mode: String,
+    build: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Archive<T> {
+    version: u32,
+    builds: Vec<T>,
+}
+
+/// What to do with a rune page whose name is taken already.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Conflict {
+    Overwrite,
+    /// Keep both, the imported one gets a number after its name.
+    Rename,
+    Skip,
+}
+
+/// Where builds are imported from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", content = "value")]
+pub enum ImportSource {
+    Code(String),
+    /// Path of an archive file.
+    Archive(String),
+}
+
+impl ImportSource {
+    pub async fn load(&self) -> Result<Vec<SharedBuild>, String> {
+        match self {
+            ImportSource::Code(code) => Ok(vec![decode_code(code)?]),
+            ImportSource::Archive(path) => {
+                let bytes = tokio::fs::read(path).await.map_err(|err| err.to_string())?;
+                decode_archive(&bytes)
+            }
+        }
+    }
+}
+
+/// Imported rune page names that are taken already, in one build file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportConflict {
+    pub file: String,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportReport {
+    pub added: Vec<String>,
+    pub overwritten: Vec<String>,
+    pub renamed: Vec<String>,
+    pub skipped: Vec<String>,
+    /// Pages or builds that can't be imported, with the reason.
+    pub rejected: Vec<String>,
+}
+
+impl TryFrom<RawSharedBuild> for SharedBuild {
+    type Error = String;
+
+    fn try_from(raw: RawSharedBuild) -> Result<Self, Self::Error> {
+        Ok(SharedBuild {
+            champion: raw.champion,
+            mode: raw.mode,
+            build: build_from_value(raw.build)?,
+        })
+    }
+}
+
+/// `lola<version>.<base64 of deflated json>.<crc32 of the json>`, short enough to paste in chat.
+pub fn encode_code(shared: &SharedBuild) -> Result<String, String> {
+    let json = serde_json::to_vec(shared).map_err(|err| err.to_string())?;
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&json).map_err(|err| err.to_string())?;
+    let deflated = encoder.finish().map_err(|err| err.to_string())?;
+    Ok(format!(
+        "{}{}.{}.{:08x}",
+        CODE_PREFIX,
+        SHARE_VERSION,
+        base64::encode_config(deflated, base64::URL_SAFE_NO_PAD),
+        crc32fast::hash(&json)
+    ))
+}
+
+pub fn decode_code(code: &str) -> Result<SharedBuild, String> {
+    // Chat apps like to wrap long lines.
+    let code: String = code.split_whitespace().collect();
+    let mut parts = code.split('.');
+    let (head, payload, checksum) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(head), Some(payload), Some(checksum), None) => (head, payload, checksum),
+        _ => return Err(String::from("Not a Lola share code.")),
+    };
+    let version: u32 = head
+        .strip_prefix(CODE_PREFIX)
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| String::from("Not a Lola share code."))?;
+    if version != SHARE_VERSION {
+        return Err(format!(
+            "Share code version {} is not supported, {} is.",
+            version, SHARE_VERSION
+        ));
+    }
+    let deflated = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| String::from("The share code is broken."))?;
+    let json = inflate(
+        DeflateDecoder::new(deflated.as_slice()),
+        "The share code is broken.",
+    )?;
+    if format!("{:08x}", crc32fast::hash(&json)) != checksum.to_lowercase() {
+        return Err(String::from("The share code is incomplete or changed."));
+    }
+    let raw: RawSharedBuild = serde_json::from_slice(&json).map_err(|err| err.to_string())?;
+    raw.try_into()
+}
+
+/// Gzipped json of all builds.
+pub fn encode_archive(builds: &[SharedBuild]) -> Result<Vec<u8>, String> {
+    let archive = Archive {
+        version: SHARE_VERSION,
+        builds: builds.to_vec(),
+    };
+    let json = serde_json::to_vec(&archive).map_err(|err| err.to_string())?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json).map_err(|err| err.to_string())?;
+    encoder.finish().map_err(|err| err.to_string())
+}
+
+pub fn decode_archive(bytes: &[u8]) -> Result<Vec<SharedBuild>, String> {
+    let json = inflate(GzDecoder::new(bytes), "Not a Lola archive.")?;
+    let archive: Archive<RawSharedBuild> =
+        serde_json::from_slice(&json).map_err(|err| err.to_string())?;
+    if archive.version != SHARE_VERSION {
+        return Err(format!(
+            "Archive version {} is not supported, {} is.",
+            archive.version, SHARE_VERSION
+        ));
+    }
+    archive
+        .builds
+        .into_iter()
+        .map(SharedBuild::try_from)
+        .collect()
+}
+
+/// Read a decoder to the end, refusing payloads that inflate past `MAX_JSON_SIZE`.
+fn inflate(decoder: impl Read, broken: &str) -> Result<Vec<u8>, String> {
+    let mut json = Vec::new();
+    decoder
+        .take(MAX_JSON_SIZE + 1)
+        .read_to_end(&mut json)
+        .map_err(|_| String::from(broken))?;
+    if json.len() as u64 > MAX_JSON_SIZE {
+        return Err(format!(
+            "Too large, more than {} MB of builds.",
+            MAX_JSON_SIZE / 1024 / 1024
+        ));
+    }
+    Ok(json)
+}
+
+/// Names of imported rune pages that are taken in `target` or by its linked templates.
+pub fn conflicts(
+    target: &CustomBuild,
+    templates: &[String],
+    incoming: &CustomBuild,
+) -> Vec<String> {
+    incoming
+        .pages
+        .iter()
+        .filter(|p| target.has_page_named(&p.page.name, None) || templates.contains(&p.page.name))
+        .map(|p| p.page.name.clone())
+        .collect()
+}
+
+/// Put the imported build into `target`. Imported pages get new ids, an overwritten
+/// page keeps its id and place. Spells, items, skill order and notes are taken if
+/// `target` has none, or on `Conflict::Overwrite`. `templates` are the names of the
+/// linked templates, a page named like one is never overwritten.
+pub fn merge(
+    target: &mut CustomBuild,
+    templates: &[String],
+    incoming: CustomBuild,
+    conflict: Conflict,
+    report: &mut ImportReport,
+) {
+    for mut page in incoming.pages {
+        let name = page.page.name.clone();
+        if templates.contains(&name) {
+            match conflict {
+                Conflict::Overwrite => report.rejected.push(format!(
+                    "{}: rune template {} is linked already",
+                    name, name
+                )),
+                Conflict::Rename => {
+                    let new_name = free_name(target, templates, &name);
+                    report.renamed.push(format!("{} -> {}", name, new_name));
+                    page.page.name = new_name;
+                    add(target, page);
+                }
+                Conflict::Skip => report.skipped.push(name),
+            }
+            continue;
+        }
+        match target.pages.iter_mut().find(|p| p.page.name == name) {
+            None => {
+                report.added.push(name);
+                add(target, page);
+            }
+            Some(existing) => match conflict {
+                Conflict::Overwrite => {
+                    report.overwritten.push(name);
+                    page.id = existing.id;
+                    *existing = page;
+                }
+                Conflict::Rename => {
+                    let new_name = free_name(target, templates, &name);
+                    report.renamed.push(format!("{} -> {}", name, new_name));
+                    page.page.name = new_name;
+                    add(target, page);
+                }
+                Conflict::Skip => report.skipped.push(name),
+            },
+        }
+    }
+    let overwrite = conflict == Conflict::Overwrite;
+    if overwrite || target.spells.is_empty() {
+        target.spells = incoming.spells;
+    }
+    if overwrite || target.items.is_empty() {
+        target.items = incoming.items;
+    }
+    if overwrite || target.skill_order.is_empty() {
+        target.skill_order = incoming.skill_order;
+    }
+    if overwrite || target.lanes.is_empty() {
+        target.lanes = incoming.lanes;
+    }
+    if overwrite || target.notes.is_empty() {
+        target.notes = incoming.notes;
+    }
+}
+
+/// Add an imported page with a new id, its tags are kept.
+fn add(target: &mut CustomBuild, page: CustomRunePage) {
+    let id = target.add_page(page.page);
+    let added = target.page_mut(id).unwrap();
+    added.lanes = page.lanes;
+    added.matchups = page.matchups;
+    added.last_used = page.last_used;
+}
+
+/// `name (2)`, or the first number after it that is not taken.
+fn free_name(build: &CustomBuild, templates: &[String], name: &str) -> String {
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !build.has_page_named(candidate, None) && !templates.contains(candidate))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{custom_provider::CUSTOM_BUILD_VERSION, source_provider::LolRuneItem};
+
+    fn page(name: &str, primary_style_id: i32) -> LolRuneItem {
+        LolRuneItem {
+            name: name.to_string(),
+            primary_style_id,
+            ..Default::default()
+        }
+    }
+
+    fn build(pages: &[(&str, i32)], spells: Vec<Vec<i32>>) -> CustomBuild {
+        let mut build = build_from_value(json!({ "version": CUSTOM_BUILD_VERSION })).unwrap();
+        for (name, style) in pages {
+            build.add_page(page(name, *style));
+        }
+        build.spells = spells;
+        build
+    }
+
+    fn shared(champion: &str) -> SharedBuild {
+        SharedBuild {
+            champion: champion.to_string(),
+            mode: String::from("CLASSIC"),
+            build: build(
+                &[("Conqueror", 8000), ("Electrocute", 8100)],
+                vec![vec![4, 14]],
+            ),
+        }
+    }
+
+    fn names(build: &CustomBuild) -> Vec<&str> {
+        build.pages.iter().map(|p| p.page.name.as_str()).collect()
+    }
+
+    #[test]
+    fn code_round_trip() {
+        let code = encode_code(&shared("Ahri")).unwrap();
+        assert!(code.starts_with("lola1."));
+        assert_eq!(decode_code(&code).unwrap(), shared("Ahri"));
+        // Wrapped by a chat app.
+        let (head, tail) = code.split_at(20);
+        assert_eq!(
+            decode_code(&format!("{}\n  {}", head, tail)).unwrap(),
+            shared("Ahri")
+        );
+    }
+
+    #[test]
+    fn changed_code_fails_the_checksum() {
+        let code = encode_code(&shared("Ahri")).unwrap();
+        let (rest, checksum) = code.rsplit_once('.').unwrap();
+        let other = if checksum == "00000000" {
+            "00000001"
+        } else {
+            "00000000"
+        };
+        let err = decode_code(&format!("{}.{}", rest, other)).unwrap_err();
+        assert!(err.contains("incomplete or changed"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_versions_and_prefixes() {
+        let code = encode_code(&shared("Ahri")).unwrap();
+        let err = decode_code(&code.replacen("lola1.", "lola2.", 1)).unwrap_err();
+        assert!(err.contains("version 2"), "{}", err);
+        let err = decode_code(&code.replacen("lola1.", "blitz1.", 1)).unwrap_err();
+        assert_eq!(err, "Not a Lola share code.");
+        assert_eq!(decode_code("lola").unwrap_err(), "Not a Lola share code.");
+    }
+
+    #[test]
+    fn archive_round_trip() {
+        let builds = vec![shared("Ahri"), shared("MonkeyKing")];
+        let bytes = encode_archive(&builds).unwrap();
+        assert_eq!(decode_archive(&bytes).unwrap(), builds);
+        assert_eq!(
+            decode_archive(b"not gzip").unwrap_err(),
+            "Not a Lola archive."
+        );
+    }
+
+    #[test]
+    fn inflating_too_much_is_refused() {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&vec![b' '; MAX_JSON_SIZE as usize + 1])
+            .unwrap();
+        let deflated = encoder.finish().unwrap();
+        let code = format!(
+            "lola1.{}.00000000",
+            base64::encode_config(deflated, base64::URL_SAFE_NO_PAD)
+        );
+        assert!(decode_code(&code).unwrap_err().starts_with("Too large"));
+    }
+
+    fn merged(conflict: Conflict) -> (CustomBuild, ImportReport) {
+        let mut target = build(
+            &[("Conqueror", 8000), ("Electrocute", 8100)],
+            vec![vec![4, 14]],
+        );
+        let incoming = build(&[("Conqueror", 8400), ("Aery", 8200)], vec![vec![4, 12]]);
+        assert_eq!(conflicts(&target, &[], &incoming), vec!["Conqueror"]);
+        let mut report = ImportReport::default();
+        merge(&mut target, &[], incoming, conflict, &mut report);
+        (target, report)
+    }
+
+    #[test]
+    fn merge_overwrite() {
+        let (target, report) = merged(Conflict::Overwrite);
+        assert_eq!(names(&target), vec!["Conqueror", "Electrocute", "Aery"]);
+        // Keeps its id and place, with the imported runes.
+        assert_eq!(target.pages[0].id, 0);
+        assert_eq!(target.pages[0].page.primary_style_id, 8400);
+        assert_eq!(target.pages[2].id, 2);
+        assert_eq!(target.spells, vec![vec![4, 12]]);
+        assert_eq!(report.overwritten, vec!["Conqueror"]);
+        assert_eq!(report.added, vec!["Aery"]);
+    }
+
+    #[test]
+    fn merge_rename() {
+        let (target, report) = merged(Conflict::Rename);
+        assert_eq!(
+            names(&target),
+            vec!["Conqueror", "Electrocute", "Conqueror (2)", "Aery"]
+        );
+        assert_eq!(target.pages[0].page.primary_style_id, 8000);
+        assert_eq!(target.pages[2].page.primary_style_id, 8400);
+        assert_eq!(target.spells, vec![vec![4, 14]]);
+        assert_eq!(report.renamed, vec!["Conqueror -> Conqueror (2)"]);
+        assert_eq!(report.added, vec!["Aery"]);
+    }
+
+    #[test]
+    fn merge_skip() {
+        let (target, report) = merged(Conflict::Skip);
+        assert_eq!(names(&target), vec!["Conqueror", "Electrocute", "Aery"]);
+        assert_eq!(target.pages[0].page.primary_style_id, 8000);
+        assert_eq!(target.spells, vec![vec![4, 14]]);
+        assert_eq!(report.skipped, vec!["Conqueror"]);
+        assert_eq!(report.added, vec!["Aery"]);
+    }
+
+    fn merged_with_template(conflict: Conflict) -> (CustomBuild, ImportReport) {
+        let mut target = build(&[("Conqueror", 8000)], vec![]);
+        let templates = vec![String::from("Aery"), String::from("Aery (2)")];
+        let incoming = build(&[("Aery", 8200)], vec![]);
+        assert_eq!(conflicts(&target, &templates, &incoming), vec!["Aery"]);
+        let mut report = ImportReport::default();
+        merge(&mut target, &templates, incoming, conflict, &mut report);
+        (target, report)
+    }
+
+    #[test]
+    fn merge_template_names() {
+        let (target, report) = merged_with_template(Conflict::Overwrite);
+        assert_eq!(names(&target), vec!["Conqueror"]);
+        assert_eq!(report.rejected.len(), 1);
+
+        let (target, report) = merged_with_template(Conflict::Rename);
+        assert_eq!(names(&target), vec!["Conqueror", "Aery (3)"]);
+        assert_eq!(report.renamed, vec!["Aery -> Aery (3)"]);
+
+        let (target, report) = merged_with_template(Conflict::Skip);
+        assert_eq!(names(&target), vec!["Conqueror"]);
+        assert_eq!(report.skipped, vec!["Aery"]);
+    }
+}
@@ -0,0 +1,729 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::fs;
+
+use super::{CustomBuild, CustomBuildChange, CustomProvider};
+use crate::{
+    source_provider::{GameMode, LolRuneItem},
+    util::write_atomic,
+};
+
+/// File of the templates in the custom builds directory, it is never taken for a build
+/// as it has no mode in its name.
+pub const TEMPLATES_FILE: &str = "templates.json";
+/// Version of the templates file, bump it and add a step to `parse_templates` on changes.
+pub const TEMPLATES_VERSION: u32 = 1;
+
+/// A rune page saved once and shown in the custom builds of every linked champion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuneTemplate {
+    /// Stays the same on edits, unique among templates.
+    pub id: u32,
+    pub page: LolRuneItem,
+    #[serde(default)]
+    pub links: Vec<TemplateLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateLink {
+    /// Ddragon id, like `MonkeyKing`.
+    pub champion: String,
+    /// Like `CLASSIC`.
+    pub mode: String,
+    #[serde(default)]
+    pub overrides: TemplateOverride,
+}
+
+/// What a champion changes of a template, the rest follows the template.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TemplateOverride {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub primary_style_id: Option<i32>,
+    #[serde(default)]
+    pub sub_style_id: Option<i32>,
+    #[serde(default)]
+    pub selected_perk_ids: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TemplateStore {
+    pub version: u32,
+    #[serde(default)]
+    pub templates: Vec<RuneTemplate>,
+    /// Id of the next added template, ids of removed ones are not reused.
+    #[serde(default)]
+    pub next_id: u32,
+}
+
+impl Default for TemplateStore {
+    fn default() -> Self {
+        TemplateStore {
+            version: TEMPLATES_VERSION,
+            templates: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl TemplateLink {
+    fn is_for(&self, champion: &str, mode: GameMode) -> bool {
+        self.champion == champion && GameMode::from_str(&self.mode) == mode
+    }
+}
+
+impl RuneTemplate {
+    /// The page as `link` sees it.
+    pub fn page_for(&self, link: &TemplateLink) -> LolRuneItem {
+        let overrides = link.overrides.clone();
+        LolRuneItem {
+            name: overrides.name.unwrap_or_else(|| self.page.name.clone()),
+            primary_style_id: overrides
+                .primary_style_id
+                .unwrap_or(self.page.primary_style_id),
+            sub_style_id: overrides.sub_style_id.unwrap_or(self.page.sub_style_id),
+            selected_perk_ids: overrides
+                .selected_perk_ids
+                .unwrap_or_else(|| self.page.selected_perk_ids.clone()),
+            ..self.page.clone()
+        }
+    }
+
+    pub fn link_mut(
+        &mut self,
+        champion: &str,
+        mode: GameMode,
+    ) -> Result<&mut TemplateLink, String> {
+        self.links
+            .iter_mut()
+            .find(|l| l.is_for(champion, mode))
+            .ok_or_else(|| {
+                format!(
+                    "Template {} is not linked to {} in {:?}",
+                    self.id, champion, mode
+                )
+            })
+    }
+}
+
+impl TemplateStore {
+    pub fn get_mut(&mut self, id: u32) -> Result<&mut RuneTemplate, String> {
+        self.templates
+            .iter_mut()
+            .find(|t| t.id == id)
+            .ok_or_else(|| format!("No rune template {}", id))
+    }
+
+    /// Whether a template other than `except` is named `name`.
+    pub fn has_named(&self, name: &str, except: Option<u32>) -> bool {
+        self.templates
+            .iter()
+            .any(|t| t.page.name == name && Some(t.id) != except)
+    }
+
+    /// Add `page` with no links, returns its id.
+    pub fn add(&mut self, page: LolRuneItem) -> u32 {
+        let id = self
+            .next_id
+            .max(self.templates.iter().map(|t| t.id + 1).max().unwrap_or(0));
+        self.next_id = id + 1;
+        self.templates.push(RuneTemplate {
+            id,
+            page,
+            links: Vec::new(),
+        });
+        id
+    }
+
+    /// Pages linked to a champion and mode, with their overrides, by template id.
+    pub fn pages_for(&self, champion: &str, mode: GameMode) -> Vec<(u32, LolRuneItem)> {
+        self.templates
+            .iter()
+            .filter_map(|t| {
+                let link = t.links.iter().find(|l| l.is_for(champion, mode))?;
+                Some((t.id, t.page_for(link)))
+            })
+            .collect()
+    }
+
+    /// Names a champion sees from templates other than `except`.
+    fn names_for(&self, champion: &str, mode: GameMode, except: u32) -> Vec<String> {
+        self.pages_for(champion, mode)
+            .into_iter()
+            .filter(|(id, _)| *id != except)
+            .map(|(_, page)| page.name)
+            .collect()
+    }
+
+    fn linked(&self) -> HashSet<(String, GameMode)> {
+        self.templates
+            .iter()
+            .flat_map(|t| &t.links)
+            .map(|l| (l.champion.clone(), GameMode::from_str(&l.mode)))
+            .collect()
+    }
+
+    /// `CustomProvider::update_template` on the store, `builds` has the custom build of
+    /// every champion and mode the template is linked to.
+    fn set_page(
+        &mut self,
+        id: u32,
+        page: LolRuneItem,
+        builds: &HashMap<(String, GameMode), CustomBuild>,
+        validate: impl Fn(&LolRuneItem) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if self.has_named(&page.name, Some(id)) {
+            return Err(format!("Rune template {} exists already", page.name));
+        }
+        validate(&page)?;
+        let mut template = self.get_mut(id)?.clone();
+        template.page = page;
+        for i in 0..template.links.len() {
+            if validate(&template.page_for(&template.links[i])).is_err() {
+                let link = &mut template.links[i];
+                tracing::info!(
+                    "Drop rune overrides of {} in {}, they don't fit template {}",
+                    link.champion,
+                    link.mode,
+                    id
+                );
+                link.overrides = TemplateOverride {
+                    name: link.overrides.name.take(),
+                    ..Default::default()
+                };
+            }
+            let link = &template.links[i];
+            let mode = GameMode::from_str(&link.mode);
+            let build = builds
+                .get(&(link.champion.clone(), mode))
+                .ok_or_else(|| format!("Rune template {} got linked meanwhile, try again", id))?;
+            let templates = self.names_for(&link.champion, mode, id);
+            check_name(&template.page_for(link).name, build, &templates)
+                .map_err(|err| format!("{} of {}", err, link.champion))?;
+        }
+        *self.get_mut(id)? = template;
+        Ok(())
+    }
+
+    /// Link a template to a champion whose custom build is `build`, linking twice does
+    /// nothing.
+    fn link(
+        &mut self,
+        id: u32,
+        champion: &str,
+        mode: GameMode,
+        build: &CustomBuild,
+    ) -> Result<(), String> {
+        let templates = self.names_for(champion, mode, id);
+        let template = self.get_mut(id)?;
+        if template.link_mut(champion, mode).is_ok() {
+            return Ok(());
+        }
+        let link = TemplateLink {
+            champion: champion.to_string(),
+            mode: mode.to_string(),
+            overrides: TemplateOverride::default(),
+        };
+        check_name(&template.page_for(&link).name, build, &templates)?;
+        template.links.push(link);
+        Ok(())
+    }
+
+    /// Set the overrides of a linked champion whose custom build is `build`.
+    fn set_overrides(
+        &mut self,
+        id: u32,
+        champion: &str,
+        mode: GameMode,
+        overrides: TemplateOverride,
+        build: &CustomBuild,
+        validate: impl Fn(&LolRuneItem) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let templates = self.names_for(champion, mode, id);
+        let template = self.get_mut(id)?;
+        let link = TemplateLink {
+            overrides,
+            ..template.link_mut(champion, mode)?.clone()
+        };
+        let page = template.page_for(&link);
+        check_name(&page.name, build, &templates)?;
+        validate(&page)?;
+        *template.link_mut(champion, mode)? = link;
+        Ok(())
+    }
+}
+
+/// Champions and modes whose template pages differ between `old` and `new`.
+pub fn changes(old: &TemplateStore, new: &TemplateStore) -> Vec<CustomBuildChange> {
+    let mut changes: Vec<CustomBuildChange> = old
+        .linked()
+        .union(&new.linked())
+        .filter(|(champion, mode)| old.pages_for(champion, *mode) != new.pages_for(champion, *mode))
+        .map(|(champion, mode)| CustomBuildChange {
+            champion_id: champion.clone(),
+            game_mode: mode.to_string(),
+            removed: false,
+        })
+        .collect();
+    changes.sort_by(|a, b| (&a.champion_id, &a.game_mode).cmp(&(&b.champion_id, &b.game_mode)));
+    changes
+}
+
+/// A champion can't see two rune pages of the same name, custom or from templates.
+fn check_name(name: &str, build: &CustomBuild, templates: &[String]) -> Result<(), String> {
+    if build.has_page_named(name, None) || templates.iter().any(|t| t == name) {
+        return Err(format!("A rune page named {} is there already", name));
+    }
+    Ok(())
+}
+
+fn parse_templates(json: &str) -> Result<TemplateStore, String> {
+    let value: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| String::from("No version"))?;
+    if version > TEMPLATES_VERSION as u64 {
+        return Err(format!(
+            "Version {} is made by a newer Lola, {} is supported.",
+            version, TEMPLATES_VERSION
+        ));
+    }
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
+
+impl CustomProvider {
+    /// Saved templates, none if there is no file.
+    pub async fn get_templates(&self) -> Result<TemplateStore, String> {
+        if let Some(store) = self.templates.read().unwrap().as_ref() {
+            return Ok(store.clone());
+        }
+        let store = self.read_templates_file().await?;
+        *self.templates.write().unwrap() = Some(store.clone());
+        Ok(store)
+    }
+
+    async fn read_templates_file(&self) -> Result<TemplateStore, String> {
+        let file = self.dir().join(TEMPLATES_FILE);
+        if !file.is_file() {
+            return Ok(TemplateStore::default());
+        }
+        let json = fs::read_to_string(&file)
+            .await
+            .map_err(|err| err.to_string())?;
+        parse_templates(&json).map_err(|err| format!("Broken rune templates {:?}: {}", file, err))
+    }
+
+    /// Read, change and save the templates like `update_build`, also returns the champions
+    /// whose custom builds look different now.
+    async fn update_templates<T>(
+        &self,
+        change: impl FnOnce(&mut TemplateStore) -> Result<T, String>,
+    ) -> Result<(T, Vec<CustomBuildChange>), String> {
+        let _guard = self.lock(TEMPLATES_FILE).await;
+        let old = self.get_templates().await?;
+        let mut store = old.clone();
+        let result = change(&mut store)?;
+        store.version = TEMPLATES_VERSION;
+        let json = serde_json::to_string(&store).map_err(|err| err.to_string())?;
+        write_atomic(&self.dir().join(TEMPLATES_FILE), json.as_bytes(), true).await?;
+        let changes = changes(&old, &store);
+        *self.templates.write().unwrap() = Some(store);
+        Ok((result, changes))
+    }
+
+    /// Re-read the templates file after it changed on disk.
+    pub(super) async fn reload_templates(&self) -> Vec<CustomBuildChange> {
+        let _guard = self.lock(TEMPLATES_FILE).await;
+        let old = self.templates.read().unwrap().clone().unwrap_or_default();
+        match self.read_templates_file().await {
+            Ok(store) => {
+                let changes = changes(&old, &store);
+                *self.templates.write().unwrap() = Some(store);
+                changes
+            }
+            Err(err) => {
+                // Reads show the error until the file is fixed.
+                tracing::error!("{}", err);
+                *self.templates.write().unwrap() = None;
+                changes(&old, &TemplateStore::default())
+            }
+        }
+    }
+
+    pub async fn template_pages(
+        &self,
+        champion_name: &str,
+        mode: GameMode,
+    ) -> Result<Vec<(u32, LolRuneItem)>, String> {
+        Ok(self.get_templates().await?.pages_for(champion_name, mode))
+    }
+
+    /// Add a template with no links, returns its id.
+    pub async fn add_template(&self, page: LolRuneItem) -> Result<u32, String> {
+        let (id, _) = self
+            .update_templates(|store| {
+                if store.has_named(&page.name, None) {
+                    return Err(format!("Rune template {} exists already", page.name));
+                }
+                Ok(store.add(page))
+            })
+            .await?;
+        Ok(id)
+    }
+
+    /// Replace the page of a template, every linked champion gets it. Overrides that
+    /// make an invalid page with the new one are dropped, but for the name.
+    pub async fn update_template(
+        &self,
+        id: u32,
+        page: LolRuneItem,
+        validate: impl Fn(&LolRuneItem) -> Result<(), String>,
+    ) -> Result<Vec<CustomBuildChange>, String> {
+        // Build files first, then the templates, like linking, so no custom page takes
+        // the new name meanwhile.
+        let files: BTreeSet<String> = self
+            .get_templates()
+            .await?
+            .templates
+            .iter()
+            .filter(|t| t.id == id)
+            .flat_map(|t| &t.links)
+            .map(|l| CustomProvider::get_file_name(&l.champion, GameMode::from_str(&l.mode)))
+            .collect();
+        let mut guards = Vec::new();
+        for file in &files {
+            guards.push(self.lock(file).await);
+        }
+        let mut builds = HashMap::new();
+        if let Some(template) = self
+            .get_templates()
+            .await?
+            .templates
+            .iter()
+            .find(|t| t.id == id)
+        {
+            for link in &template.links {
+                let mode = GameMode::from_str(&link.mode);
+                let build = self.get_build(&link.champion, mode).await?;
+                builds.insert((link.champion.clone(), mode), build);
+            }
+        }
+        let (_, changes) = self
+            .update_templates(|store| store.set_page(id, page, &builds, validate))
+            .await?;
+        Ok(changes)
+    }
+
+    pub async fn remove_template(&self, id: u32) -> Result<Vec<CustomBuildChange>, String> {
+        let (_, changes) = self
+            .update_templates(|store| {
+                store.get_mut(id)?;
+                store.templates.retain(|t| t.id != id);
+                Ok(())
+            })
+            .await?;
+        Ok(changes)
+    }
+
+    /// Show a template in the custom builds of a champion, linking twice does nothing.
+    /// Refused if the champion has a rune page of its name.
+    pub async fn link_template(
+        &self,
+        id: u32,
+        champion_name: &str,
+        mode: GameMode,
+    ) -> Result<Vec<CustomBuildChange>, String> {
+        // Held so no custom page takes the name meanwhile.
+        let _guard = self
+            .lock(&CustomProvider::get_file_name(champion_name, mode))
+            .await;
+        let build = self.get_build(champion_name, mode).await?;
+        let (_, changes) = self
+            .update_templates(|store| store.link(id, champion_name, mode, &build))
+            .await?;
+        Ok(changes)
+    }
+
+    /// Remove a template from a champion, its overrides are dropped too.
+    pub async fn unlink_template(
+        &self,
+        id: u32,
+        champion_name: &str,
+        mode: GameMode,
+    ) -> Result<Vec<CustomBuildChange>, String> {
+        let (_, changes) = self
+            .update_templates(|store| {
+                let template = store.get_mut(id)?;
+                template.link_mut(champion_name, mode)?;
+                template.links.retain(|l| !l.is_for(champion_name, mode));
+                Ok(())
+            })
+            .await?;
+        Ok(changes)
+    }
+
+    /// Refused if the template with the overrides is not a page `validate` accepts, or
+    /// is named like another page of the champion.
+    pub async fn set_template_override(
+        &self,
+        id: u32,
+        champion_name: &str,
+        mode: GameMode,
+        overrides: TemplateOverride,
+        validate: impl Fn(&LolRuneItem) -> Result<(), String>,
+    ) -> Result<Vec<CustomBuildChange>, String> {
+        let _guard = self
+            .lock(&CustomProvider::get_file_name(champion_name, mode))
+            .await;
+        let build = self.get_build(champion_name, mode).await?;
+        let (_, changes) = self
+            .update_templates(|store| {
+                store.set_overrides(id, champion_name, mode, overrides, &build, validate)
+            })
+            .await?;
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::custom_provider::{build_from_value, CUSTOM_BUILD_VERSION};
+
+    fn page(name: &str, primary_style_id: i32, sub_style_id: i32) -> LolRuneItem {
+        LolRuneItem {
+            name: name.to_string(),
+            primary_style_id,
+            sub_style_id,
+            selected_perk_ids: vec![8010, 9111],
+            ..Default::default()
+        }
+    }
+
+    fn link(champion: &str, overrides: TemplateOverride) -> TemplateLink {
+        TemplateLink {
+            champion: champion.to_string(),
+            mode: String::from("CLASSIC"),
+            overrides,
+        }
+    }
+
+    fn build(pages: &[&str]) -> CustomBuild {
+        let mut build = build_from_value(json!({ "version": CUSTOM_BUILD_VERSION })).unwrap();
+        for name in pages {
+            build.add_page(page(name, 8000, 8100));
+        }
+        build
+    }
+
+    /// Conqueror linked to Ahri and to Garen with overrides, Aery linked to Ahri.
+    fn store() -> TemplateStore {
+        let mut store = TemplateStore::default();
+        let conqueror = store.add(page("Conqueror", 8000, 8100));
+        let aery = store.add(page("Aery", 8200, 8300));
+        store.get_mut(conqueror).unwrap().links = vec![
+            link("Ahri", TemplateOverride::default()),
+            link(
+                "Garen",
+                TemplateOverride {
+                    name: Some(String::from("Garen Conqueror")),
+                    sub_style_id: Some(8400),
+                    ..Default::default()
+                },
+            ),
+        ];
+        store.get_mut(aery).unwrap().links = vec![link("Ahri", TemplateOverride::default())];
+        store
+    }
+
+    /// Builds of every linked champion, with the given custom pages.
+    fn builds(pages: &[(&str, &[&str])]) -> HashMap<(String, GameMode), CustomBuild> {
+        pages
+            .iter()
+            .map(|(champion, pages)| ((champion.to_string(), GameMode::Classic), build(pages)))
+            .collect()
+    }
+
+    /// Rejects pages whose two trees are the same.
+    fn validate(page: &LolRuneItem) -> Result<(), String> {
+        if page.primary_style_id == page.sub_style_id {
+            return Err(String::from("Same trees"));
+        }
+        Ok(())
+    }
+
+    fn changed(old: &TemplateStore, new: &TemplateStore) -> Vec<String> {
+        changes(old, new)
+            .into_iter()
+            .map(|c| format!("{} {}", c.champion_id, c.game_mode))
+            .collect()
+    }
+
+    #[test]
+    fn page_for_takes_overrides() {
+        let store = store();
+        let template = &store.templates[0];
+        assert_eq!(template.page_for(&template.links[0]), template.page);
+
+        let page = template.page_for(&template.links[1]);
+        assert_eq!(page.name, "Garen Conqueror");
+        assert_eq!(page.sub_style_id, 8400);
+        // The rest follows the template.
+        assert_eq!(page.primary_style_id, 8000);
+        assert_eq!(page.selected_perk_ids, template.page.selected_perk_ids);
+    }
+
+    #[test]
+    fn changes_of_edit_link_and_unlink() {
+        let old = store();
+        assert!(changed(&old, &old).is_empty());
+
+        let mut edited = old.clone();
+        edited.get_mut(0).unwrap().page.selected_perk_ids = vec![8005];
+        assert_eq!(
+            changed(&old, &edited),
+            vec!["Ahri CLASSIC", "Garen CLASSIC"]
+        );
+
+        let mut linked = old.clone();
+        linked
+            .link(1, "Garen", GameMode::Classic, &build(&[]))
+            .unwrap();
+        assert_eq!(changed(&old, &linked), vec!["Garen CLASSIC"]);
+
+        let mut unlinked = old.clone();
+        unlinked.get_mut(1).unwrap().links.clear();
+        assert_eq!(changed(&old, &unlinked), vec!["Ahri CLASSIC"]);
+    }
+
+    #[test]
+    fn set_page_drops_overrides_that_no_longer_fit() {
+        let mut store = store();
+        let builds = builds(&[("Ahri", &[]), ("Garen", &[])]);
+        store
+            .set_page(0, page("Conqueror", 8400, 8000), &builds, validate)
+            .unwrap();
+        let template = &store.templates[0];
+        assert_eq!(template.page.primary_style_id, 8400);
+        assert_eq!(template.links[0].overrides, TemplateOverride::default());
+        // Garen's sub tree would be the new primary one, only the name is kept.
+        assert_eq!(
+            template.links[1].overrides,
+            TemplateOverride {
+                name: Some(String::from("Garen Conqueror")),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn set_page_name_clashes() {
+        let mut store = store();
+        let old = store.clone();
+        let builds = builds(&[("Ahri", &["Electrocute"]), ("Garen", &[])]);
+        let err = store
+            .set_page(0, page("Electrocute", 8000, 8100), &builds, validate)
+            .unwrap_err();
+        assert!(err.ends_with("of Ahri"), "{}", err);
+        assert!(store
+            .set_page(0, page("Aery", 8000, 8100), &builds, validate)
+            .is_err());
+        // Every linked build is needed to check the name.
+        let garen_only = self::builds(&[("Garen", &[])]);
+        assert!(store
+            .set_page(0, page("Comet", 8000, 8100), &garen_only, validate)
+            .is_err());
+        assert_eq!(store, old);
+    }
+
+    #[test]
+    fn link_name_clashes() {
+        let mut store = store();
+        // Garen has a custom page named like the template.
+        assert!(store
+            .link(1, "Garen", GameMode::Classic, &build(&["Aery"]))
+            .is_err());
+        // Garen sees Conqueror under its override name.
+        let id = store.add(page("Garen Conqueror", 8000, 8100));
+        assert!(store
+            .link(id, "Garen", GameMode::Classic, &build(&[]))
+            .is_err());
+        assert!(store.get_mut(id).unwrap().links.is_empty());
+
+        store
+            .link(1, "Garen", GameMode::Classic, &build(&[]))
+            .unwrap();
+        store
+            .link(1, "Garen", GameMode::Classic, &build(&[]))
+            .unwrap();
+        assert_eq!(store.templates[1].links.len(), 2);
+    }
+
+    #[test]
+    fn set_overrides_name_clashes() {
+        let mut store = store();
+        let named = |name: &str| TemplateOverride {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+        let ahri = build(&["Comet"]);
+        // Taken by the Conqueror template and by a custom page.
+        assert!(store
+            .set_overrides(
+                1,
+                "Ahri",
+                GameMode::Classic,
+                named("Conqueror"),
+                &ahri,
+                validate
+            )
+            .is_err());
+        assert!(store
+            .set_overrides(
+                1,
+                "Ahri",
+                GameMode::Classic,
+                named("Comet"),
+                &ahri,
+                validate
+            )
+            .is_err());
+        let same_trees = TemplateOverride {
+            sub_style_id: Some(8200),
+            ..Default::default()
+        };
+        assert!(store
+            .set_overrides(1, "Ahri", GameMode::Classic, same_trees, &ahri, validate)
+            .is_err());
+        assert_eq!(store, self::store());
+
+        store
+            .set_overrides(
+                1,
+                "Ahri",
+                GameMode::Classic,
+                named("Ahri Aery"),
+                &ahri,
+                validate,
+            )
+            .unwrap();
+        assert_eq!(
+            store.pages_for("Ahri", GameMode::Classic)[1].1.name,
+            "Ahri Aery"
+        );
+    }
+
+    #[test]
+    fn newer_version_is_rejected() {
+        let current = json!({ "version": TEMPLATES_VERSION }).to_string();
+        assert_eq!(parse_templates(&current).unwrap(), TemplateStore::default());
+        let newer = json!({ "version": TEMPLATES_VERSION + 1 }).to_string();
+        assert!(parse_templates(&newer).unwrap_err().starts_with("Version"));
+    }
+}
@@ -1,20 +1,94 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
 
-use jsonpath_rust::JsonPathFinder;
+use async_trait::async_trait;
+use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+
+use crate::{
+    asset_cache::asset_url,
+    champion_id::{self, ChampionId},
+    champion_search::{self, Candidate, ChampionMatch},
+    http_client::HttpClient,
+    source_provider::{Build, GameMode, RuneItem, SpellItem},
+    static_data::{
+        cdragon::CommunityDragon, Augment, Backend, DataKind, Routing, StatShard, StaticData,
+    },
+    util::write_atomic,
+};
+
+use self::diff::VersionDiff;
+
+pub mod diff;
+
+const DDRAGON_URL: &str = "https://ddragon.leagueoflegends.com";
+
+/// Stat shards per row, they are not in ddragon. Old and new shards are both accepted.
+const STAT_SHARDS: [&[i32]; 3] = [
+    &[5008, 5005, 5007],
+    &[5008, 5010, 5001, 5002, 5003],
+    &[5011, 5013, 5001, 5002, 5003],
+];
+
+/// Name and icon (under `cdn/img/`) of stat shards, which ddragon doesn't list.
+const STAT_SHARD_INFO: [(i32, &str, &str); 9] = [
+    (5001, "Health Scaling", "StatModsHealthScalingIcon.png"),
+    (5002, "Armor", "StatModsArmorIcon.png"),
+    (5003, "Magic Resist", "StatModsMagicResIcon.png"),
+    (5005, "Attack Speed", "StatModsAttackSpeedIcon.png"),
+    (5007, "Ability Haste", "StatModsCDRScalingIcon.png"),
+    (5008, "Adaptive Force", "StatModsAdaptiveForceIcon.png"),
+    (5010, "Move Speed", "StatModsMovementSpeedIcon.png"),
+    (5011, "Health", "StatModsHealthPlusIcon.png"),
+    (5013, "Tenacity and Slow Resist", "StatModsTenacityIcon.png"),
+];
 
 #[derive(Debug)]
 pub struct DDragon {
+    /// Swapped as a whole when a newer version is downloaded.
+    data: RwLock<Arc<DDragonData>>,
+    /// Every version is saved in `<version>/<language>.json` under it.
+    store_dir: PathBuf,
+    language: RwLock<Language>,
+    /// Champion names of every language loaded since start, keyed by champion key.
+    champion_names: RwLock<HashMap<Language, HashMap<String, String>>>,
+    /// Backend of each kind of data, used by the next download.
+    routing: RwLock<Routing>,
+    cdragon: CommunityDragon,
+}
+
+/// All static data of one ddragon version, it is what gets saved to disk.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DDragonData {
+    /// Keyed by the numeric champion key, like `"62"`.
     champions: HashMap<String, Champion>,
+    rune_trees: Vec<RuneTree>,
+    /// Keyed by the numeric spell id.
+    summoner_spells: HashMap<i32, SummonerSpell>,
+    items: HashMap<i32, Item>,
+    maps: Vec<GameMap>,
+    queues: Vec<Queue>,
+    #[serde(default)]
+    stat_shards: Vec<StatShard>,
+    #[serde(default)]
+    augments: Vec<Augment>,
     version: String,
+}
 
-    #[allow(dead_code)]
-    language: Language, //prepare for future
+impl DDragonData {
+    /// Empty until data is loaded or downloaded, nothing can be checked then.
+    fn is_loaded(&self) -> bool {
+        !self.rune_trees.is_empty() && !self.summoner_spells.is_empty()
+    }
 }
 
 impl DDragon {
-    pub async fn get_latest_version() -> Result<String, String> {
-        let versions = Self::get_versions().await;
+    pub async fn get_latest_version(http: &HttpClient) -> Result<String, String> {
+        let versions = Self::get_versions(http).await;
         match versions {
             Ok(versions) => {
                 if let Some(first) = versions.first() {
@@ -27,81 +101,832 @@ impl DDragon {
         }
     }
 
-    pub async fn get_versions() -> Result<Vec<String>, String> {
-        let resp = reqwest::get("https://ddragon.leagueoflegends.com/api/versions.json").await;
-        match resp {
-            Ok(resp) => {
-                let versions: Vec<String> = resp.json().await.expect("Convert json error!");
-                Ok(versions)
-            }
-
-            Err(err) => Err(err.to_string()),
-        }
+    pub async fn get_versions(http: &HttpClient) -> Result<Vec<String>, String> {
+        http.get_json(&format!("{}/api/versions.json", DDRAGON_URL))
+            .await
     }
 
-    pub async fn get_champions(
+    pub async fn get_champions_online(
+        http: &HttpClient,
         version: &str,
         language: Language,
     ) -> Result<HashMap<String, Champion>, String> {
-        let resp = reqwest::get(format!(
-            "http://ddragon.leagueoflegends.com/cdn/{}/data/{}/champion.json",
+        let json: DataJson<Champion> = http
+            .get_json(&format!(
+                "{}/cdn/{}/data/{}/champion.json",
+                DDRAGON_URL,
+                version,
+                language.to_string()
+            ))
+            .await?;
+        Ok(json
+            .data
+            .into_values()
+            .map(|c| (c.key.clone(), c))
+            .collect())
+    }
+
+    pub async fn get_rune_trees_online(
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Vec<RuneTree>, String> {
+        http.get_json(&format!(
+            "{}/cdn/{}/data/{}/runesReforged.json",
+            DDRAGON_URL,
             version,
             language.to_string()
         ))
         .await
-        .expect("Get champion.json error!");
-        let json = resp.text().await.expect("Get json text error!");
-        let name_finder = JsonPathFinder::from_str(&json, "$.data..name")?;
-        let id_finder = JsonPathFinder::from_str(&json, "$.data..id")?;
-        let key_finder = JsonPathFinder::from_str(&json, "$.data..key")?;
-
-        let names = name_finder.find_slice();
-        let ids = id_finder.find_slice();
-        let keys = key_finder.find_slice();
-        if !(names.len() == ids.len() && ids.len() == keys.len()) {
-            return Err(String::from(
-                "Champions name, id and key length is no same!",
-            )); //error because they no same.
-        }
-
-        let mut champions = HashMap::with_capacity(names.len());
-        for i in 0..names.len() {
-            champions.insert(
-                keys[i].as_str().unwrap().to_owned(),
-                Champion {
-                    name: names[i].as_str().unwrap().to_owned(),
-                    id: ids[i].as_str().unwrap().to_owned(),
-                    key: keys[i].as_str().unwrap().to_owned(),
-                },
-            );
+    }
+
+    pub async fn get_summoner_spells_online(
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<HashMap<i32, SummonerSpell>, String> {
+        let json: DataJson<SummonerSpell> = http
+            .get_json(&format!(
+                "{}/cdn/{}/data/{}/summoner.json",
+                DDRAGON_URL,
+                version,
+                language.to_string()
+            ))
+            .await?;
+        json.data
+            .into_values()
+            .map(|spell| match spell.key.parse() {
+                Ok(key) => Ok((key, spell)),
+                Err(_) => Err(format!("Bad key {} of spell {}", spell.key, spell.id)),
+            })
+            .collect()
+    }
+
+    pub async fn get_items_online(
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<HashMap<i32, Item>, String> {
+        let json: DataJson<Item> = http
+            .get_json(&format!(
+                "{}/cdn/{}/data/{}/item.json",
+                DDRAGON_URL,
+                version,
+                language.to_string()
+            ))
+            .await?;
+        json.data
+            .into_iter()
+            .map(|(id, item)| match id.parse() {
+                Ok(id) => Ok((id, Item { id, ..item })),
+                Err(_) => Err(format!("Bad item id {}", id)),
+            })
+            .collect()
+    }
+
+    pub async fn get_maps_online(
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Vec<GameMap>, String> {
+        let json: DataJson<GameMap> = http
+            .get_json(&format!(
+                "{}/cdn/{}/data/{}/map.json",
+                DDRAGON_URL,
+                version,
+                language.to_string()
+            ))
+            .await?;
+        let mut maps: Vec<GameMap> = json.data.into_values().collect();
+        maps.sort_by(|a, b| a.map_id.cmp(&b.map_id));
+        Ok(maps)
+    }
+
+    /// Queues are not in ddragon, riot keeps them with the api docs.
+    pub async fn get_queues_online(http: &HttpClient) -> Result<Vec<Queue>, String> {
+        http.get_json("https://static.developer.riotgames.com/docs/lol/queues.json")
+            .await
+    }
+
+    /// Start with the newest saved data, or empty and let `refresh` download it later,
+    /// so a bad network never holds up the start.
+    pub async fn new(language: Language, routing: Routing) -> Self {
+        let proj = ProjectDirs::from("com", "Jinte", "Lola").expect("Can't find data path.");
+        let ddragon = DDragon {
+            data: RwLock::new(Arc::new(DDragonData::default())),
+            store_dir: proj.data_dir().join("ddragon"),
+            language: RwLock::new(language),
+            champion_names: RwLock::new(HashMap::new()),
+            routing: RwLock::new(routing),
+            cdragon: CommunityDragon,
+        };
+        match ddragon.load_local().await {
+            Ok(data) => {
+                info!("Loaded ddragon {} from disk.", data.version);
+                ddragon.remember_names(&data, language);
+                *ddragon.data.write().unwrap() = Arc::new(data);
+            }
+            Err(err) => info!("No usable local ddragon, start without it: {}", err),
         }
+        ddragon
+    }
+
+    /// Download the latest version if it is newer than the one in use.
+    /// Return `true` if the data is replaced.
+    pub async fn refresh(&self, http: &HttpClient) -> Result<bool, String> {
+        let version = DDragon::get_latest_version(http).await?;
+        let current = self.get_version();
+        if !current.is_empty() && !is_newer_patch(&version, &current) {
+            return Ok(false);
+        }
+        let language = self.get_language();
+        info!("Download ddragon {} of {}.", version, language.to_string());
+        let data = self.download(http, &version, language).await?;
+        self.save_local(&data, language).await?;
+        self.swap(data, language)
+    }
+
+    pub fn get_language(&self) -> Language {
+        *self.language.read().unwrap()
+    }
+
+    /// Use data of another language, saved or downloaded, for the same version.
+    /// Return `true` if the language changed.
+    pub async fn set_language(&self, http: &HttpClient, language: Language) -> Result<bool, String> {
+        if self.get_language() == language {
+            return Ok(false);
+        }
+        let version = self.get_version();
+        if version.is_empty() {
+            *self.language.write().unwrap() = language;
+            self.refresh(http).await?;
+            return Ok(true);
+        }
+
+        let data = match self.load_version(&version, language).await {
+            Ok(data) => data,
+            Err(_) => {
+                info!("Download ddragon {} of {}.", version, language.to_string());
+                let data = self.download(http, &version, language).await?;
+                self.save_local(&data, language).await?;
+                data
+            }
+        };
+        *self.language.write().unwrap() = language;
+        self.swap(data, language)?;
+        Ok(true)
+    }
 
-        Ok(champions)
+    /// Use the data unless the language was switched while it was downloaded.
+    fn swap(&self, data: DDragonData, language: Language) -> Result<bool, String> {
+        if self.get_language() != language {
+            return Err(String::from("Language changed, drop the downloaded ddragon."));
+        }
+        self.remember_names(&data, language);
+        *self.data.write().unwrap() = Arc::new(data);
+        Ok(true)
+    }
+
+    fn remember_names(&self, data: &DDragonData, language: Language) {
+        let names = data
+            .champions
+            .iter()
+            .map(|(key, c)| (key.clone(), c.name.clone()))
+            .collect();
+        self.champion_names.write().unwrap().insert(language, names);
+    }
+
+    pub fn set_routing(&self, routing: Routing) {
+        *self.routing.write().unwrap() = routing;
+    }
+
+    /// Backend that the kind of data is routed to.
+    fn source(&self, kind: DataKind) -> &dyn StaticData {
+        match self.routing.read().unwrap().backend(kind) {
+            Backend::DDragon => self,
+            Backend::CommunityDragon => &self.cdragon,
+        }
+    }
+
+    async fn download(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<DDragonData, String> {
+        let (champions, rune_trees, summoner_spells, items, maps, queues, stat_shards, augments) = tokio::join!(
+            self.source(DataKind::Champions).get_champions(http, version, language),
+            self.source(DataKind::RuneTrees).get_rune_trees(http, version, language),
+            self.source(DataKind::SummonerSpells).get_summoner_spells(http, version, language),
+            self.source(DataKind::Items).get_items(http, version, language),
+            self.source(DataKind::Maps).get_maps(http, version, language),
+            self.source(DataKind::Queues).get_queues(http, version, language),
+            self.source(DataKind::StatShards).get_stat_shards(http, version, language),
+            self.source(DataKind::Augments).get_augments(http, version, language),
+        );
+        // Builds can't be shown without the first three, the others are only for display.
+        let log_failed = |name: &str, err: String| {
+            error!("Get lol {} data failed: {}", name, err);
+        };
+        Ok(DDragonData {
+            champions: champions?,
+            rune_trees: rune_trees?,
+            summoner_spells: summoner_spells?,
+            items: items.unwrap_or_else(|err| {
+                log_failed("items", err);
+                HashMap::new()
+            }),
+            maps: maps.unwrap_or_else(|err| {
+                log_failed("maps", err);
+                Vec::new()
+            }),
+            queues: queues.unwrap_or_else(|err| {
+                log_failed("queues", err);
+                Vec::new()
+            }),
+            // Runes still show with the shards ddragon knows.
+            stat_shards: stat_shards.unwrap_or_else(|err| {
+                log_failed("stat shards", err);
+                Vec::new()
+            }),
+            augments: augments.unwrap_or_else(|err| {
+                log_failed("augments", err);
+                Vec::new()
+            }),
+            version: version.to_owned(),
+        })
+    }
+
+    /// Compare champions, items and runes of two versions in the current language.
+    pub async fn diff_versions(
+        &self,
+        http: &HttpClient,
+        old: &str,
+        new: &str,
+    ) -> Result<VersionDiff, String> {
+        let versions = DDragon::get_versions(http).await?;
+        for version in [old, new] {
+            if !versions.iter().any(|v| v == version) {
+                return Err(format!("No ddragon version {}", version));
+            }
+        }
+        let language = self.get_language();
+        let (old, new) = tokio::try_join!(
+            self.load_for_diff(http, old, language),
+            self.load_for_diff(http, new, language)
+        )?;
+        Ok(diff::diff(&old, &new))
     }
 
-    pub async fn new(language: Language) -> Self {
-        let version = DDragon::get_latest_version()
+    /// Data of the version in use, saved or downloaded without saving it.
+    async fn load_for_diff(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Arc<DDragonData>, String> {
+        let current = self.data();
+        if current.version == version && self.get_language() == language {
+            return Ok(current);
+        }
+        if let Ok(data) = self.load_version(version, language).await {
+            return Ok(Arc::new(data));
+        }
+        info!("Download ddragon {} of {} to compare.", version, language.to_string());
+        let (champions, rune_trees, items) = tokio::try_join!(
+            self.source(DataKind::Champions).get_champions(http, version, language),
+            self.source(DataKind::RuneTrees).get_rune_trees(http, version, language),
+            self.source(DataKind::Items).get_items(http, version, language),
+        )?;
+        Ok(Arc::new(DDragonData {
+            champions,
+            rune_trees,
+            items,
+            version: version.to_owned(),
+            ..Default::default()
+        }))
+    }
+
+    /// Newest saved version of the language.
+    async fn load_local(&self) -> Result<DDragonData, String> {
+        let mut versions = Vec::new();
+        let mut dir = tokio::fs::read_dir(&self.store_dir)
             .await
-            .expect("Get latest lol client version failed!");
-        DDragon {
-            champions: DDragon::get_champions(&version, language)
-                .await
-                .expect("Get lol champions data failed!"),
-            version,
-            language,
+            .map_err(|err| err.to_string())?;
+        while let Some(entry) = dir.next_entry().await.map_err(|err| err.to_string())? {
+            if let Some(version) = entry.file_name().to_str() {
+                versions.push(version.to_owned());
+            }
         }
+        versions.sort_by(|a, b| {
+            if is_newer_patch(a, b) {
+                std::cmp::Ordering::Less
+            } else if is_newer_patch(b, a) {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+        let language = self.get_language();
+        for version in versions {
+            match self.load_version(&version, language).await {
+                Ok(data) => return Ok(data),
+                Err(err) => error!("{}", err),
+            }
+        }
+        Err(String::from("No saved ddragon version."))
     }
 
-    pub fn get_champion_information(&self, key: &str) -> Result<Champion, String> {
-        if let Some(c) = self.champions.get(key) {
-            return Ok(c.clone());
+    async fn load_version(&self, version: &str, language: Language) -> Result<DDragonData, String> {
+        let file = self.data_file(version, language);
+        let json = tokio::fs::read_to_string(&file)
+            .await
+            .map_err(|err| format!("Read ddragon file {:?} error: {}", file, err))?;
+        serde_json::from_str(&json).map_err(|err| format!("Broken ddragon file {:?}: {}", file, err))
+    }
+
+    /// Save the data and remove older versions.
+    async fn save_local(&self, data: &DDragonData, language: Language) -> Result<(), String> {
+        let file = self.data_file(&data.version, language);
+        tokio::fs::create_dir_all(file.parent().unwrap())
+            .await
+            .map_err(|err| err.to_string())?;
+        let json = serde_json::to_string(data).map_err(|err| err.to_string())?;
+        // A half written file would be loaded as broken next time.
+        write_atomic(&file, json.as_bytes(), false).await?;
+
+        let mut dir = tokio::fs::read_dir(&self.store_dir)
+            .await
+            .map_err(|err| err.to_string())?;
+        while let Some(entry) = dir.next_entry().await.map_err(|err| err.to_string())? {
+            let name = entry.file_name();
+            if name.to_str() != Some(data.version.as_str()) {
+                info!("Remove old ddragon {:?}", name);
+                if let Err(err) = tokio::fs::remove_dir_all(entry.path()).await {
+                    error!("{:?}", err);
+                }
+            }
         }
+        Ok(())
+    }
+
+    fn data_file(&self, version: &str, language: Language) -> PathBuf {
+        self.store_dir
+            .join(version)
+            .join(format!("{}.json", language.to_string()))
+    }
+
+    fn data(&self) -> Arc<DDragonData> {
+        self.data.read().unwrap().clone()
+    }
 
-        Err(String::from(format!("Key value {} no match with ddragon!", key)))
+    pub fn get_champion_information(&self, key: &str) -> Result<Champion, String> {
+        let champion = self.resolve_champion(key)?;
+        self.get_champion(&champion)
+            .ok_or_else(|| format!("Key value {} no match with ddragon!", key))
     }
 
     pub fn get_version(&self) -> String {
-        self.version.to_owned()
+        self.data().version.to_owned()
+    }
+
+    /// Patch of the latest version, like `13.5`.
+    pub fn get_patch(&self) -> String {
+        patch_of(&self.data().version)
+    }
+
+    /// Check the page is one the client accepts, anything passes while no data is loaded.
+    pub fn validate_rune(&self, rune: &RuneItem) -> Result<(), String> {
+        check_rune(&self.data(), rune)
+    }
+
+    /// Check the spells can be taken together in the mode, anything passes while no data is loaded.
+    pub fn validate_spells(&self, spells: &SpellItem, mode: GameMode) -> Result<(), String> {
+        check_spells(&self.data(), spells, mode)
+    }
+
+    /// Drop runes and spells that can't be used, with the reasons kept in the build.
+    pub fn reject_invalid(&self, build: Build, mode: GameMode) -> Build {
+        reject_invalid(&self.data(), build, mode)
+    }
+
+    /// `lola-asset` url of a versioned image, like a champion or item icon.
+    pub fn image_url(&self, image: &Image) -> String {
+        let data = self.data();
+        asset_url(&format!(
+            "cdn/{}/img/{}/{}",
+            data.version, image.group, image.full
+        ))
+    }
+
+    /// Rune trees, runes and stat shards by id.
+    pub fn get_rune_infos(&self) -> HashMap<i32, StaticInfo> {
+        let data = self.data();
+        let mut infos = HashMap::new();
+        for tree in &data.rune_trees {
+            infos.insert(
+                tree.id,
+                StaticInfo {
+                    id: tree.id,
+                    name: tree.name.clone(),
+                    description: String::new(),
+                    icon: asset_url(&format!("cdn/img/{}", tree.icon)),
+                },
+            );
+            for rune in tree.slots.iter().flat_map(|slot| &slot.runes) {
+                infos.insert(
+                    rune.id,
+                    StaticInfo {
+                        id: rune.id,
+                        name: rune.name.clone(),
+                        description: rune.short_desc.clone(),
+                        icon: asset_url(&format!("cdn/img/{}", rune.icon)),
+                    },
+                );
+            }
+        }
+        let shards = if data.stat_shards.is_empty() {
+            ddragon_stat_shards()
+        } else {
+            data.stat_shards.clone()
+        };
+        for shard in shards {
+            infos.insert(
+                shard.id,
+                StaticInfo {
+                    id: shard.id,
+                    name: shard.name,
+                    description: shard.description,
+                    icon: asset_url(&shard.icon),
+                },
+            );
+        }
+        infos
+    }
+
+    /// Arena augments with their icon urls.
+    pub fn get_augments(&self) -> Vec<Augment> {
+        self.data()
+            .augments
+            .iter()
+            .map(|augment| Augment {
+                icon: asset_url(&augment.icon),
+                ..augment.clone()
+            })
+            .collect()
+    }
+
+    pub fn get_rune_trees(&self) -> Vec<RuneTree> {
+        self.data().rune_trees.clone()
+    }
+
+    pub fn get_spell_infos(&self) -> HashMap<i32, StaticInfo> {
+        let data = self.data();
+        data.summoner_spells
+            .iter()
+            .map(|(id, spell)| {
+                (
+                    *id,
+                    StaticInfo {
+                        id: *id,
+                        name: spell.name.clone(),
+                        description: spell.description.clone(),
+                        icon: self.image_url(&spell.image),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    pub fn get_item_infos(&self) -> HashMap<i32, StaticInfo> {
+        let data = self.data();
+        data.items
+            .iter()
+            .map(|(id, item)| {
+                (
+                    *id,
+                    StaticInfo {
+                        id: *id,
+                        name: item.name.clone(),
+                        description: item.plaintext.clone(),
+                        icon: self.image_url(&item.image),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    pub fn get_item(&self, id: i32) -> Option<Item> {
+        self.data().items.get(&id).cloned()
+    }
+
+    pub fn get_summoner_spell(&self, id: i32) -> Option<SummonerSpell> {
+        self.data().summoner_spells.get(&id).cloned()
+    }
+
+    pub fn get_maps(&self) -> Vec<GameMap> {
+        self.data().maps.clone()
+    }
+
+    pub fn get_queues(&self) -> Vec<Queue> {
+        self.data().queues.clone()
+    }
+
+    pub fn get_queue(&self, queue_id: i32) -> Option<Queue> {
+        self.data().queues.iter().find(|q| q.queue_id == queue_id).cloned()
+    }
+
+    /// Champion by its lcu id, ddragon id or localized name, then by the
+    /// names and slugs `ChampionId` knows.
+    pub fn resolve_champion(&self, s: &str) -> Result<ChampionId, String> {
+        let data = self.data();
+        let name = champion_id::normalize(s);
+        let found = data.champions.values().find(|c| {
+            c.key == s.trim()
+                || c.id.eq_ignore_ascii_case(s.trim())
+                || champion_id::normalize(&c.name) == name
+        });
+        if let Some(champion) = found {
+            if let Ok(key) = champion.key.parse() {
+                return Ok(ChampionId::new(key, &champion.id));
+            }
+        }
+        ChampionId::resolve(s).ok_or_else(|| format!("Unknown champion {}", s))
+    }
+
+    pub fn get_champion(&self, champion: &ChampionId) -> Option<Champion> {
+        self.data().champions.get(&champion.key().to_string()).cloned()
+    }
+
+    /// Champions matching `query` in any loaded language, by nickname or with typos.
+    pub fn search_champions(&self, query: &str, limit: usize) -> Vec<ChampionMatch> {
+        let data = self.data();
+        let names = self.champion_names.read().unwrap();
+        let candidates = data
+            .champions
+            .iter()
+            .filter_map(|(key, c)| {
+                let champion = ChampionId::new(key.parse().ok()?, &c.id);
+                let mut all: Vec<String> = names
+                    .values()
+                    .filter_map(|names| names.get(key).cloned())
+                    .collect();
+                all.push(c.id.clone());
+                all.extend(champion.english_name().map(String::from));
+                Some(Candidate {
+                    key: champion.key(),
+                    id: c.id.clone(),
+                    name: c.name.clone(),
+                    names: all,
+                    aliases: champion.aliases(),
+                })
+            })
+            .collect();
+        champion_search::search(candidates, query, limit)
+    }
+
+    /// All champions, sorted by ddragon id.
+    pub fn get_champion_ids(&self) -> Vec<ChampionId> {
+        let data = self.data();
+        let mut ids: Vec<ChampionId> = data
+            .champions
+            .values()
+            .filter_map(|c| Some(ChampionId::new(c.key.parse().ok()?, &c.id)))
+            .collect();
+        ids.sort_by(|a, b| a.id().cmp(b.id()));
+        ids
+    }
+}
+
+#[async_trait]
+impl StaticData for DDragon {
+    fn name(&self) -> &'static str {
+        "DDragon"
+    }
+
+    async fn get_champions(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<HashMap<String, Champion>, String> {
+        DDragon::get_champions_online(http, version, language).await
+    }
+
+    async fn get_rune_trees(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Vec<RuneTree>, String> {
+        DDragon::get_rune_trees_online(http, version, language).await
+    }
+
+    async fn get_summoner_spells(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<HashMap<i32, SummonerSpell>, String> {
+        DDragon::get_summoner_spells_online(http, version, language).await
+    }
+
+    async fn get_items(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<HashMap<i32, Item>, String> {
+        DDragon::get_items_online(http, version, language).await
+    }
+
+    async fn get_maps(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Vec<GameMap>, String> {
+        DDragon::get_maps_online(http, version, language).await
+    }
+
+    async fn get_queues(
+        &self,
+        http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<Vec<Queue>, String> {
+        DDragon::get_queues_online(http).await
+    }
+
+    async fn get_stat_shards(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<Vec<StatShard>, String> {
+        Ok(ddragon_stat_shards())
+    }
+}
+
+/// Check the page is one the client accepts: a keystone and a rune per row of
+/// the primary tree, two runes of different rows of another tree, and a shard per row.
+fn check_rune(data: &DDragonData, rune: &RuneItem) -> Result<(), String> {
+    if data.rune_trees.is_empty() {
+        return Ok(());
+    }
+    let tree = |id: i32| {
+        data.rune_trees
+            .iter()
+            .find(|t| t.id == id)
+            .ok_or(format!("Unknown rune tree {}", id))
+    };
+    let primary = tree(rune.primary_page_id)?;
+    let secondary = tree(rune.secondary_page_id)?;
+    if primary.id == secondary.id {
+        return Err(format!("Secondary tree is the same as primary {}", primary.name));
+    }
+
+    if rune.primary_rune_ids.len() != primary.slots.len() {
+        return Err(format!(
+            "{} needs {} runes, got {}",
+            primary.name,
+            primary.slots.len(),
+            rune.primary_rune_ids.len()
+        ));
+    }
+    for (row, (slot, id)) in primary.slots.iter().zip(&rune.primary_rune_ids).enumerate() {
+        if !slot.contains(*id) {
+            return Err(format!("Rune {} is not in row {} of {}", id, row, primary.name));
+        }
+    }
+
+    if rune.secondary_rune_ids.len() != 2 {
+        return Err(format!(
+            "Secondary tree needs 2 runes, got {}",
+            rune.secondary_rune_ids.len()
+        ));
+    }
+    let mut rows = Vec::with_capacity(2);
+    for id in &rune.secondary_rune_ids {
+        // Keystones can't be taken from the secondary tree.
+        let row = secondary
+            .slots
+            .iter()
+            .skip(1)
+            .position(|slot| slot.contains(*id))
+            .ok_or(format!("Rune {} is not a minor rune of {}", id, secondary.name))?;
+        if rows.contains(&row) {
+            return Err(format!("Two secondary runes of the same row of {}", secondary.name));
+        }
+        rows.push(row);
+    }
+
+    if rune.stat_mod_ids.len() != STAT_SHARDS.len() {
+        return Err(format!(
+            "Needs {} stat shards, got {}",
+            STAT_SHARDS.len(),
+            rune.stat_mod_ids.len()
+        ));
+    }
+    for (row, (shards, id)) in STAT_SHARDS.iter().zip(&rune.stat_mod_ids).enumerate() {
+        if !shards.contains(id) {
+            return Err(format!("Stat shard {} is not in row {}", id, row));
+        }
+    }
+    Ok(())
+}
+
+/// Check the two spells are different and both can be taken in the mode.
+fn check_spells(data: &DDragonData, spells: &SpellItem, mode: GameMode) -> Result<(), String> {
+    if data.summoner_spells.is_empty() {
+        return Ok(());
+    }
+    if spells.ids.len() != 2 {
+        return Err(format!("Needs 2 summoner spells, got {}", spells.ids.len()));
+    }
+    if spells.ids[0] == spells.ids[1] {
+        return Err(format!("Summoner spell {} is taken twice", spells.ids[0]));
+    }
+    for id in &spells.ids {
+        let spell = data
+            .summoner_spells
+            .get(id)
+            .ok_or(format!("Unknown summoner spell {}", id))?;
+        if !spell.modes.contains(&mode.to_string()) {
+            return Err(format!("{} is not available in {}", spell.name, mode.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Drop what fails the checks, the build is marked unchecked when no data is loaded.
+fn reject_invalid(data: &DDragonData, mut build: Build, mode: GameMode) -> Build {
+    let mut rejected = Vec::new();
+    build.runes.retain(|rune| match check_rune(data, rune) {
+        Ok(_) => true,
+        Err(err) => {
+            rejected.push(format!("Rune {}: {}", rune.name, err));
+            false
+        }
+    });
+    build.spells.retain(|spell| match check_spells(data, spell, mode) {
+        Ok(_) => true,
+        Err(err) => {
+            rejected.push(format!("Spells {:?}: {}", spell.ids, err));
+            false
+        }
+    });
+    for reason in &rejected {
+        warn!("Rejected from build: {}", reason);
+    }
+    build.rejected = rejected;
+    build.unchecked = !data.is_loaded();
+    build
+}
+
+/// Stat shards known without downloading, named in english.
+fn ddragon_stat_shards() -> Vec<StatShard> {
+    STAT_SHARD_INFO
+        .iter()
+        .map(|(id, name, icon)| StatShard {
+            id: *id,
+            name: name.to_string(),
+            description: String::new(),
+            icon: format!("cdn/img/perk-images/StatMods/{}", icon),
+        })
+        .collect()
+}
+
+/// Patch of a game or ddragon version, like `13.5.495.4427` or `13.5.1` to `13.5`.
+pub fn patch_of(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+/// Whether `patch` is newer than `than`. Patches that can't be parsed are never newer.
+pub fn is_newer_patch(patch: &str, than: &str) -> bool {
+    let parse = |p: &str| -> Option<Vec<u32>> { p.split('.').map(|n| n.parse().ok()).collect() };
+    match (parse(patch), parse(than)) {
+        (Some(patch), Some(than)) => patch > than,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+impl Language {
+    pub fn all() -> [Language; 27] {
+        use Language::*;
+        [
+            en_US, cs_CZ, de_DE, el_GR, en_AU, en_GB, en_PH, en_SG, es_AR, es_ES, es_MX, fr_FR,
+            hu_HU, id_ID, it_IT, ja_JP, ko_KR, pl_PL, pt_BR, ro_RO, ru_RU, th_TH, tr_TR, vn_VN,
+            zh_CN, zh_MY, zh_TW,
+        ]
+    }
+
+    /// Language of a locale like `zh_CN`, `None` if ddragon has no such language.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        Language::all()
+            .into_iter()
+            .find(|l| l.to_string().eq_ignore_ascii_case(locale))
     }
 }
 
@@ -139,16 +964,170 @@ impl ToString for Language {
     }
 }
 
+/// Shape of ddragon data files, like `champion.json`.
+#[derive(Debug, Deserialize)]
+struct DataJson<T> {
+    data: HashMap<String, T>,
+}
+
+/// What the UI needs to show a rune, spell or item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticInfo {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    pub icon: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Image {
+    pub full: String,
+    /// Directory of the image under `img/`, like `champion`.
+    pub group: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Champion {
     pub id: String,
     pub key: String,
     pub name: String,
+    pub title: String,
+    /// Roles, like `Mage` or `Assassin`.
+    pub tags: Vec<String>,
+    /// Resource, like `Mana` or `Energy`.
+    pub partype: String,
+    pub image: Image,
+    #[serde(default)]
+    pub stats: ChampionStats,
+}
+
+/// `None` when the version has no such stat, older ones lack `attackspeed` for one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChampionStats {
+    pub hp: Option<f64>,
+    pub hpperlevel: Option<f64>,
+    pub mp: Option<f64>,
+    pub mpperlevel: Option<f64>,
+    pub movespeed: Option<f64>,
+    pub armor: Option<f64>,
+    pub armorperlevel: Option<f64>,
+    pub spellblock: Option<f64>,
+    pub spellblockperlevel: Option<f64>,
+    pub attackrange: Option<f64>,
+    pub hpregen: Option<f64>,
+    pub hpregenperlevel: Option<f64>,
+    pub mpregen: Option<f64>,
+    pub mpregenperlevel: Option<f64>,
+    pub attackdamage: Option<f64>,
+    pub attackdamageperlevel: Option<f64>,
+    pub attackspeedperlevel: Option<f64>,
+    pub attackspeed: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Item {
+    /// Not in the json, set from the key of `data`.
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    /// Html description with stats.
+    pub description: String,
+    #[serde(default)]
+    pub plaintext: String,
+    pub image: Image,
+    pub gold: ItemGold,
+    /// Stat name to value, like `FlatHPPoolMod`.
+    #[serde(default)]
+    pub stats: HashMap<String, f64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Items it builds into.
+    #[serde(default)]
+    pub into: Vec<String>,
+    /// Items it builds from.
+    #[serde(default)]
+    pub from: Vec<String>,
+    /// Map id to whether the item can be bought there.
+    #[serde(default)]
+    pub maps: HashMap<String, bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemGold {
+    pub base: i32,
+    pub total: i32,
+    pub sell: i32,
+    pub purchasable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameMap {
+    #[serde(rename = "MapName")]
+    pub map_name: String,
+    #[serde(rename = "MapId")]
+    pub map_id: String,
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Queue {
+    pub queue_id: i32,
+    pub map: String,
+    /// Localized name, only CommunityDragon has it.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `None` for custom games.
+    pub description: Option<String>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuneTree {
+    pub id: i32,
+    pub key: String,
+    pub icon: String,
+    pub name: String,
+    /// The first slot is of keystones.
+    pub slots: Vec<RuneSlot>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuneSlot {
+    pub runes: Vec<Rune>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rune {
+    pub id: i32,
+    pub key: String,
+    pub icon: String,
+    pub name: String,
+    pub short_desc: String,
+    pub long_desc: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummonerSpell {
+    pub id: String,
+    /// Numeric id as string, like `"4"` of flash.
+    pub key: String,
+    pub name: String,
+    pub description: String,
+    pub image: Image,
+    /// Game modes the spell can be taken in, like `CLASSIC`.
+    pub modes: Vec<String>,
+}
+
+impl RuneSlot {
+    fn contains(&self, id: i32) -> bool {
+        self.runes.iter().any(|r| r.id == id)
+    }
 }
 
 #[allow(non_camel_case_types)]
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Language {
     en_US,
     cs_CZ,
@@ -178,3 +1157,40 @@ pub enum Language {
     zh_MY,
     zh_TW,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build() -> Build {
+        Build {
+            runes: vec![RuneItem {
+                primary_page_id: 8000,
+                primary_rune_ids: vec![8005, 9111, 9104, 8014],
+                secondary_page_id: 8100,
+                secondary_rune_ids: vec![8139, 8135],
+                stat_mod_ids: vec![5008, 5008, 5002],
+                ..Default::default()
+            }],
+            spells: vec![SpellItem {
+                ids: vec![4, 14],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn nothing_is_rejected_without_data() {
+        let data = DDragonData::default();
+        let build = build();
+        assert_eq!(check_rune(&data, &build.runes[0]), Ok(()));
+        assert_eq!(check_spells(&data, &build.spells[0], GameMode::Classic), Ok(()));
+
+        let checked = reject_invalid(&data, build.clone(), GameMode::Classic);
+        assert_eq!(checked.runes, build.runes);
+        assert_eq!(checked.spells, build.spells);
+        assert!(checked.rejected.is_empty());
+        assert!(checked.unchecked);
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::DDragonData;
+
+/// What changed in static data from one ddragon version to another.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VersionDiff {
+    pub old: String,
+    pub new: String,
+    pub added_champions: Vec<Named>,
+    pub removed_champions: Vec<Named>,
+    /// Champions whose base or per level stats changed.
+    pub champions: Vec<Changed>,
+    pub added_items: Vec<Named>,
+    pub removed_items: Vec<Named>,
+    /// Items whose stats or costs changed.
+    pub items: Vec<Changed>,
+    pub runes: Vec<RuneChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Named {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Changed {
+    pub id: String,
+    pub name: String,
+    pub changes: Vec<StatChange>,
+}
+
+/// `None` if the stat is not there in that version.
+#[derive(Debug, Clone, Serialize)]
+pub struct StatChange {
+    pub stat: String,
+    pub old: Option<f64>,
+    pub new: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuneChange {
+    pub id: i32,
+    pub name: String,
+    pub old: String,
+    pub new: String,
+}
+
+pub(super) fn diff(old: &DDragonData, new: &DDragonData) -> VersionDiff {
+    let mut diff = VersionDiff {
+        old: old.version.clone(),
+        new: new.version.clone(),
+        ..Default::default()
+    };
+
+    for (key, champion) in &new.champions {
+        let named = Named {
+            id: champion.id.clone(),
+            name: champion.name.clone(),
+        };
+        match old.champions.get(key) {
+            None => diff.added_champions.push(named),
+            Some(before) => {
+                let changes = compare(&numbers(&before.stats), &numbers(&champion.stats));
+                if !changes.is_empty() {
+                    diff.champions.push(Changed {
+                        id: named.id,
+                        name: named.name,
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+    diff.removed_champions = old
+        .champions
+        .iter()
+        .filter(|(key, _)| !new.champions.contains_key(*key))
+        .map(|(_, c)| Named {
+            id: c.id.clone(),
+            name: c.name.clone(),
+        })
+        .collect();
+
+    for (id, item) in &new.items {
+        let named = Named {
+            id: id.to_string(),
+            name: item.name.clone(),
+        };
+        match old.items.get(id) {
+            None => diff.added_items.push(named),
+            Some(before) => {
+                let mut changes = compare(&numbers(&before.gold), &numbers(&item.gold));
+                for change in &mut changes {
+                    change.stat = format!("gold.{}", change.stat);
+                }
+                changes.extend(compare(&sorted(&before.stats), &sorted(&item.stats)));
+                if !changes.is_empty() {
+                    diff.items.push(Changed {
+                        id: named.id,
+                        name: named.name,
+                        changes,
+                    });
+                }
+            }
+        }
+    }
+    diff.removed_items = old
+        .items
+        .iter()
+        .filter(|(id, _)| !new.items.contains_key(*id))
+        .map(|(id, item)| Named {
+            id: id.to_string(),
+            name: item.name.clone(),
+        })
+        .collect();
+
+    let old_runes: HashMap<i32, &str> = old
+        .rune_trees
+        .iter()
+        .flat_map(|tree| tree.slots.iter().flat_map(|slot| slot.runes.iter()))
+        .map(|rune| (rune.id, rune.long_desc.as_str()))
+        .collect();
+    for rune in new
+        .rune_trees
+        .iter()
+        .flat_map(|tree| tree.slots.iter().flat_map(|slot| slot.runes.iter()))
+    {
+        if let Some(before) = old_runes.get(&rune.id) {
+            if *before != rune.long_desc {
+                diff.runes.push(RuneChange {
+                    id: rune.id,
+                    name: rune.name.clone(),
+                    old: before.to_string(),
+                    new: rune.long_desc.clone(),
+                });
+            }
+        }
+    }
+
+    diff.added_champions.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.removed_champions.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.champions.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.added_items.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.removed_items.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.items.sort_by(|a, b| a.name.cmp(&b.name));
+    diff.runes.sort_by_key(|rune| rune.id);
+    diff
+}
+
+/// Numeric fields of a struct, by name.
+fn numbers<T: Serialize>(value: &T) -> BTreeMap<String, f64> {
+    match serde_json::to_value(value) {
+        Ok(Value::Object(map)) => map
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value.as_f64()?)))
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+fn sorted(stats: &HashMap<String, f64>) -> BTreeMap<String, f64> {
+    stats.iter().map(|(name, value)| (name.clone(), *value)).collect()
+}
+
+fn compare(old: &BTreeMap<String, f64>, new: &BTreeMap<String, f64>) -> Vec<StatChange> {
+    let mut stats: Vec<&String> = old.keys().chain(new.keys()).collect();
+    stats.sort();
+    stats.dedup();
+    stats
+        .into_iter()
+        .filter_map(|stat| {
+            let (old, new) = (old.get(stat).copied(), new.get(stat).copied());
+            (old != new).then(|| StatChange {
+                stat: stat.clone(),
+                old,
+                new,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = include_str!("../../tests/fixtures/ddragon/data-old.json");
+    const NEW: &str = include_str!("../../tests/fixtures/ddragon/data-new.json");
+
+    fn fixtures() -> VersionDiff {
+        let old: DDragonData = serde_json::from_str(OLD).unwrap();
+        let new: DDragonData = serde_json::from_str(NEW).unwrap();
+        diff(&old, &new)
+    }
+
+    fn change(changed: &Changed, stat: &str) -> (Option<f64>, Option<f64>) {
+        let change = changed.changes.iter().find(|c| c.stat == stat).unwrap();
+        (change.old, change.new)
+    }
+
+    #[test]
+    fn champion_stats() {
+        let diff = fixtures();
+        assert_eq!((diff.old.as_str(), diff.new.as_str()), ("4.1.2", "14.24.1"));
+        assert_eq!(diff.champions.len(), 1);
+        let ahri = &diff.champions[0];
+        assert_eq!(ahri.name, "Ahri");
+        assert_eq!(ahri.changes.len(), 2);
+        assert_eq!(change(ahri, "hp"), (Some(514.0), Some(526.0)));
+        // The old version has no attack speed.
+        assert_eq!(change(ahri, "attackspeed"), (None, Some(0.668)));
+        assert!(diff.added_champions.is_empty() && diff.removed_champions.is_empty());
+    }
+
+    #[test]
+    fn item_gold() {
+        let diff = fixtures();
+        assert_eq!(diff.items.len(), 1);
+        let boots = &diff.items[0];
+        assert_eq!(boots.id, "1001");
+        let stats: Vec<&str> = boots.changes.iter().map(|c| c.stat.as_str()).collect();
+        assert_eq!(stats, vec!["gold.base", "gold.sell", "gold.total"]);
+        assert_eq!(change(boots, "gold.total"), (Some(300.0), Some(350.0)));
+    }
+
+    #[test]
+    fn rune_text() {
+        let diff = fixtures();
+        assert_eq!(diff.runes.len(), 1);
+        assert_eq!(diff.runes[0].id, 8005);
+        assert!(diff.runes[0].old.contains("40 - 180"));
+        assert!(diff.runes[0].new.contains("40 - 160"));
+    }
+}
@@ -1,17 +1,31 @@
-use std::{sync::Arc, process::Command};
+use std::{collections::HashMap, path::Path, sync::Arc, process::Command, time::Duration};
 
 use crate::{
     app_config::AppConfig,
     app_status::AppStatus,
-    custom_provider::CustomProvider,
-    ddragon::{self, DDragon},
+    cache_warmer::{CacheWarmer, WarmerProgress},
+    custom_provider::{
+        Conflict, CustomBuild, CustomBuildChange, CustomProvider, ImportConflict, ImportReport,
+        ImportSource, RuneTemplate, SharedBuild, TemplateOverride,
+    },
+    ddragon::{self, diff::VersionDiff, DDragon, Language, StaticInfo},
+    http_client::HttpClient,
+    asset_cache::AssetCache,
+    champion_id::ChampionId,
+    champion_search::ChampionMatch,
+    static_data::Augment,
     lcu_driver::LcuDriver,
-    source_provider::{Build, DynSource, GameMode, Lane, LolRuneItem},
+    source_provider::{
+        cache_manager::{CacheEntryInfo, CacheStats},
+        Build, DynSource, FetchMode, GameMode, Lane, LolRuneItem, RuneItem,
+    },
 };
 use futures_util::lock::Mutex;
+use serde::Serialize;
 use serde_json::Value;
 use tauri::{AppHandle, Manager};
-use tokio::{join, try_join};
+use futures_util::future::join_all;
+use tokio::{sync::broadcast::error::RecvError, try_join};
 use tracing::{error, info, instrument};
 
 #[instrument]
@@ -72,15 +86,59 @@ pub async fn lcu_delete(lcu: tauri::State<'_, LcuDriver>, url: String) -> Result
 }
 
 #[tauri::command]
-#[instrument]
+#[instrument(skip(provider))]
 pub async fn initialize(
     app: tauri::AppHandle,
     lcu: tauri::State<'_, LcuDriver>,
+    provider: tauri::State<'_, DynSource>,
+    config: tauri::State<'_, Mutex<AppConfig>>,
 ) -> Result<(), String> {
-    lcu.hook_on_window(app).await;
+    lcu.hook_on_window(app.clone()).await;
+    match lcu.get_game_patch().await {
+        Ok(patch) => provider.set_patch(&patch).await,
+        Err(err) => error!("Get game patch from client failed: {}", err),
+    }
+    let language = config.lock().await.language();
+    if let Err(err) = apply_language(&app, language).await {
+        error!("Apply language failed: {}", err);
+    }
     Ok(())
 }
 
+/// Switch ddragon to the language, or to the client's one if `None`.
+/// Emit `ddragon_updated` if it changed.
+async fn apply_language(handle: &AppHandle, language: Option<Language>) -> Result<(), String> {
+    let language = match language {
+        Some(language) => language,
+        None => match handle.try_state::<LcuDriver>() {
+            Some(lcu) => {
+                let locale = lcu.get_locale().await?;
+                Language::from_locale(&locale).unwrap_or_else(|| {
+                    info!("No ddragon language of locale {}, use english.", locale);
+                    Language::en_US
+                })
+            }
+            // Not connected yet, `initialize` applies it later.
+            None => return Ok(()),
+        },
+    };
+    let http = handle.state::<HttpClient>().inner().clone();
+    let ddragon = handle.state::<DDragon>();
+    if ddragon.set_language(&http, language).await? {
+        info!("Ddragon language is {} now.", language.to_string());
+        handle
+            .emit_all("ddragon_updated", ddragon.get_version())
+            .unwrap();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[instrument]
+pub async fn get_languages() -> Result<Vec<String>, String> {
+    Ok(Language::all().iter().map(|l| l.to_string()).collect())
+}
+
 #[tauri::command]
 #[instrument]
 pub async fn get_app_status(
@@ -136,126 +194,307 @@ pub async fn show_in_folder(path: String) {
 }
 
 #[tauri::command]
-#[instrument]
+#[instrument(skip(provider))]
 pub async fn get_champion_build(
     champion_id: String,
     lane: String,
     game_mode: String,
-    provider: tauri::State<'_, Mutex<DynSource>>,
+    provider: tauri::State<'_, DynSource>,
     custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
 ) -> Result<Build, String> {
+    let mode = GameMode::from_str(&game_mode);
+    let champion = ddragon.resolve_champion(&champion_id)?;
     if lane == "custom" {
         info!("Get custom from local..");
-        let runes = custom
-            .get_champion_runes(&champion_id, GameMode::from_str(&game_mode))
-            .await;
-        return Ok(Build {
-            runes,
-            spells: Vec::new(),
-            is_cache: true,
-            is_custom: true,
-            lane,
-        });
+        let runes = custom.get_champion_runes(champion.id(), mode).await?;
+        let custom = custom.get_build(champion.id(), mode).await?;
+        return Ok(ddragon.reject_invalid(
+            Build {
+                runes,
+                spells: custom.spell_items(),
+                is_cache: true,
+                is_custom: true,
+                lane,
+                patch: String::new(),
+                rejected: Vec::new(),
+                unchecked: false,
+                items: custom.items,
+                skill_order: custom.skill_order,
+                notes: custom.notes,
+            },
+            mode,
+        ));
     }
 
-    let info = provider
-        .lock()
-        .await
-        .get_champion_info(
-            &champion_id,
-            Lane::from_str(&lane).unwrap(),
-            GameMode::from_str(&game_mode),
-        )
-        .await
-        .unwrap();
+    get_provider_build(
+        provider.inner().clone(),
+        champion.clone(),
+        Lane::from_str(&lane)?,
+        mode,
+    )
+    .await
+    .map(|build| prepare_build(&ddragon, build, &champion, mode))
+    .map_err(|err| {
+        error!(error=%err);
+        err
+    })
+}
 
-    let (runes, spells) = join!(info.get_runes(), info.get_spells());
-    let runes = match runes {
-        Ok(runes) => runes,
-        Err(err) => {
-            error!(error=%err);
-            return Err(err);
-        }
-    };
+#[tauri::command]
+#[instrument(skip(provider))]
+pub async fn get_champion_all_build(
+    champion_id: String,
+    game_mode: String,
+    provider: tauri::State<'_, DynSource>,
+    ddragon: tauri::State<'_, DDragon>,
+) -> Result<Vec<Build>, String> {
+    let mode = GameMode::from_str(&game_mode);
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    let tasks: Vec<_> = [Lane::Top, Lane::Jungle, Lane::Mid, Lane::Bot, Lane::Support]
+        .into_iter()
+        .map(|lane| {
+            tokio::spawn(get_provider_build(
+                provider.inner().clone(),
+                champion.clone(),
+                lane,
+                mode,
+            ))
+        })
+        .collect();
 
-    let spells = match spells {
-        Ok(spells) => spells,
-        Err(err) => {
-            error!(error=%err);
-            return Err(err);
+    let mut builds = Vec::with_capacity(tasks.len());
+    let mut last_err = None;
+    for result in join_all(tasks).await {
+        match result.map_err(|err| err.to_string()).and_then(|r| r) {
+            Ok(build) => builds.push(prepare_build(&ddragon, build, &champion, mode)),
+            Err(err) => {
+                error!(error=%err);
+                last_err = Some(err);
+            }
         }
-    };
+    }
+
+    match last_err {
+        Some(err) if builds.is_empty() => Err(err),
+        _ => Ok(builds),
+    }
+}
+
+async fn get_provider_build(
+    provider: DynSource,
+    champion: ChampionId,
+    lane: Lane,
+    mode: GameMode,
+) -> Result<Build, String> {
+    let info = provider.get_champion_info(&champion, lane, mode).await?;
+    let (runes, spells) = try_join!(info.get_runes(), info.get_spells())?;
 
     Ok(Build {
         runes,
         spells,
         is_cache: info.is_cache(),
         is_custom: false,
-        lane,
+        lane: info.get_lane().to_string(),
+        patch: info.get_patch(),
+        ..Default::default()
     })
 }
 
-#[tauri::command]
-#[instrument]
-pub async fn get_champion_all_build(
+/// Name the rune pages of a provider build with the localized champion name,
+/// then drop what can't be used.
+fn prepare_build(ddragon: &DDragon, mut build: Build, champion: &ChampionId, mode: GameMode) -> Build {
+    let champion_name = ddragon
+        .get_champion(champion)
+        .map(|c| c.name)
+        .unwrap_or_else(|| champion.id().to_owned());
+    for rune in &mut build.runes {
+        rune.name = format!(
+            "[Lola] {}-{} ({:.2}%/{} games)",
+            champion_name,
+            build.lane,
+            rune.win as f64 / rune.play.max(1) as f64 * 100.0,
+            rune.play
+        );
+    }
+    ddragon.reject_invalid(build, mode)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BuildUpdatedEvent {
     champion_id: String,
     game_mode: String,
-    provider: tauri::State<'_, Mutex<DynSource>>,
-) -> Result<Vec<Build>, String> {
-    let builds: Arc<Mutex<Vec<Build>>> = Arc::new(Mutex::new(Vec::with_capacity(5)));
-    let p = provider.lock().await;
-    let all = try_join!(
-        p.get_champion_info(&champion_id, Lane::Top, GameMode::from_str(&game_mode)),
-        p.get_champion_info(&champion_id, Lane::Jungle, GameMode::from_str(&game_mode)),
-        p.get_champion_info(&champion_id, Lane::Mid, GameMode::from_str(&game_mode)),
-        p.get_champion_info(&champion_id, Lane::Bot, GameMode::from_str(&game_mode)),
-        p.get_champion_info(&champion_id, Lane::Support, GameMode::from_str(&game_mode)),
-    );
-    let (top, jg, mid, bot, sp) = all.unwrap();
-    let lanes = [top, jg, mid, bot, sp];
-    for lane in lanes {
-        let builds = builds.clone();
-        tokio::spawn(async move {
-            let (runes, spells) = join!(lane.get_runes(), lane.get_spells());
-            let runes = match runes {
-                Ok(runes) => runes,
-                Err(err) => {
-                    error!(error=%err);
-                    return;
-                }
-            };
+    build: Build,
+}
 
-            let spells = match spells {
-                Ok(spells) => spells,
+/// Emit `build_updated` to the window whenever the provider refreshed a build in background.
+pub async fn emit_build_updates(handle: AppHandle) {
+    let provider = handle.state::<DynSource>().inner().clone();
+    let mut updates = provider.subscribe_updates();
+    loop {
+        let update = match updates.recv().await {
+            Ok(update) => update,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        match get_provider_build(
+            provider.clone(),
+            update.champion.clone(),
+            update.lane,
+            update.mode,
+        )
+        .await
+        {
+            Ok(build) => {
+                let build = prepare_build(
+                    &handle.state::<DDragon>(),
+                    build,
+                    &update.champion,
+                    update.mode,
+                );
+                handle
+                    .emit_all(
+                        "build_updated",
+                        BuildUpdatedEvent {
+                            champion_id: update.champion.id().to_owned(),
+                            game_mode: update.mode.to_string(),
+                            build,
+                        },
+                    )
+                    .unwrap()
+            }
+            Err(err) => error!(error=%err),
+        }
+    }
+}
+
+/// Emit `custom_builds_changed` when custom build files are changed by others, like
+/// a sync tool or a text editor.
+pub async fn emit_custom_build_changes(handle: AppHandle) {
+    let custom = handle.state::<CustomProvider>();
+    while let Some(changes) = custom.next_changes().await {
+        if !changes.is_empty() {
+            handle.emit_all("custom_builds_changed", changes).unwrap();
+        }
+    }
+}
+
+/// Check for a newer ddragon version now and then, and emit `ddragon_updated` once it is in use.
+pub async fn refresh_ddragon(handle: AppHandle) {
+    loop {
+        let provider = handle.state::<DynSource>().inner().clone();
+        let wait = if let FetchMode::Offline = provider.fetch_mode() {
+            Duration::from_secs(60 * 60)
+        } else {
+            let http = handle.state::<HttpClient>().inner().clone();
+            let ddragon = handle.state::<DDragon>();
+            match ddragon.refresh(&http).await {
+                Ok(updated) => {
+                    if updated {
+                        provider.set_patch(&ddragon.get_patch()).await;
+                        handle
+                            .state::<AssetCache>()
+                            .prune(&ddragon.get_version())
+                            .await;
+                        handle
+                            .emit_all("ddragon_updated", ddragon.get_version())
+                            .unwrap();
+                    }
+                    Duration::from_secs(60 * 60)
+                }
                 Err(err) => {
-                    error!(error=%err);
-                    return;
+                    error!("Refresh ddragon failed: {}", err);
+                    Duration::from_secs(5 * 60)
                 }
-            };
-            builds.lock().await.push(Build {
-                runes,
-                spells,
-                is_cache: lane.is_cache(),
-                is_custom: false,
-                lane: lane.get_lane().to_string(),
-            });
-        })
-        .await
-        .unwrap();
+            }
+        };
+        tokio::time::sleep(wait).await;
     }
+}
 
-    let builds = &*builds.lock().await;
-    Ok(builds.to_owned())
+/// Keep the last drift report in `AppStatus` and emit `schema_drift` to the window.
+pub async fn emit_schema_drift(handle: AppHandle) {
+    let mut drift = handle.state::<DynSource>().subscribe_drift();
+    loop {
+        let report = match drift.recv().await {
+            Ok(report) => report,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => break,
+        };
+        handle
+            .state::<Arc<Mutex<AppStatus>>>()
+            .lock()
+            .await
+            .schema_drift = Some(report.clone());
+        handle.emit_all("schema_drift", report).unwrap();
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(provider))]
+pub async fn clear_cache(provider: tauri::State<'_, DynSource>) -> Result<(), String> {
+    provider.clear_cache().await;
+
+    Ok(())
+}
+
+#[tauri::command]
+#[instrument(skip(provider))]
+pub async fn cache_stats(provider: tauri::State<'_, DynSource>) -> Result<CacheStats, String> {
+    provider.cache_stats().await
+}
+
+#[tauri::command]
+#[instrument(skip(provider))]
+pub async fn list_cache_entries(
+    provider: tauri::State<'_, DynSource>,
+) -> Result<Vec<CacheEntryInfo>, String> {
+    provider.list_cache_entries().await
+}
+
+#[tauri::command]
+#[instrument(skip(provider))]
+pub async fn evict_cache_entry(
+    champion_id: String,
+    lane: String,
+    game_mode: String,
+    provider: tauri::State<'_, DynSource>,
+) -> Result<bool, String> {
+    provider
+        .evict_cache_entry(
+            &champion_id,
+            Lane::from_str(&lane)?,
+            GameMode::from_str(&game_mode),
+        )
+        .await
 }
 
 #[tauri::command]
 #[instrument]
-pub async fn clear_cache(provider: tauri::State<'_, Mutex<DynSource>>) -> Result<(), String> {
-    provider.lock().await.clear_cache().await;
+pub async fn start_cache_warmer(
+    handle: AppHandle,
+    warmer: tauri::State<'_, CacheWarmer>,
+    config: tauri::State<'_, Mutex<AppConfig>>,
+) -> Result<(), String> {
+    let options = config.lock().await.warmer_options();
+    warmer.start(handle, options)
+}
 
+#[tauri::command]
+#[instrument]
+pub async fn stop_cache_warmer(warmer: tauri::State<'_, CacheWarmer>) -> Result<(), String> {
+    warmer.stop();
     Ok(())
 }
 
+#[tauri::command]
+#[instrument]
+pub async fn get_cache_warmer_progress(
+    warmer: tauri::State<'_, CacheWarmer>,
+) -> Result<WarmerProgress, String> {
+    Ok(warmer.get_progress().await)
+}
+
 #[tauri::command]
 #[instrument]
 pub async fn get_champion_raw_info(
@@ -280,50 +519,299 @@ pub async fn get_champion_icon(
     ddragon: tauri::State<'_, DDragon>,
     champion_id: String,
 ) -> Result<String, String> {
-    let id = ddragon.get_champion_information(&champion_id).unwrap().id;
-    Ok(format!(
-        "http://ddragon.leagueoflegends.com/cdn/{}/img/champion/{}.png",
-        ddragon.get_version(),
-        id
-    ))
+    let champion = ddragon.get_champion_information(&champion_id)?;
+    Ok(ddragon.image_url(&champion.image))
 }
 
 #[tauri::command]
-#[instrument]
+#[instrument(skip(ddragon))]
+pub async fn get_rune_infos(
+    ddragon: tauri::State<'_, DDragon>,
+) -> Result<HashMap<i32, StaticInfo>, String> {
+    Ok(ddragon.get_rune_infos())
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_rune_trees(
+    ddragon: tauri::State<'_, DDragon>,
+) -> Result<Vec<ddragon::RuneTree>, String> {
+    Ok(ddragon.get_rune_trees())
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_spell_infos(
+    ddragon: tauri::State<'_, DDragon>,
+) -> Result<HashMap<i32, StaticInfo>, String> {
+    Ok(ddragon.get_spell_infos())
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_item_infos(
+    ddragon: tauri::State<'_, DDragon>,
+) -> Result<HashMap<i32, StaticInfo>, String> {
+    Ok(ddragon.get_item_infos())
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_item(ddragon: tauri::State<'_, DDragon>, id: i32) -> Result<ddragon::Item, String> {
+    ddragon.get_item(id).ok_or(format!("No found item {}", id))
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_summoner_spell(
+    ddragon: tauri::State<'_, DDragon>,
+    id: i32,
+) -> Result<ddragon::SummonerSpell, String> {
+    ddragon
+        .get_summoner_spell(id)
+        .ok_or(format!("No found summoner spell {}", id))
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_maps(ddragon: tauri::State<'_, DDragon>) -> Result<Vec<ddragon::GameMap>, String> {
+    Ok(ddragon.get_maps())
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_queues(ddragon: tauri::State<'_, DDragon>) -> Result<Vec<ddragon::Queue>, String> {
+    Ok(ddragon.get_queues())
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_queue(
+    ddragon: tauri::State<'_, DDragon>,
+    queue_id: i32,
+) -> Result<ddragon::Queue, String> {
+    ddragon
+        .get_queue(queue_id)
+        .ok_or(format!("No found queue {}", queue_id))
+}
+
+#[tauri::command]
+#[instrument(skip(http))]
+pub async fn get_ddragon_versions(
+    http: tauri::State<'_, HttpClient>,
+) -> Result<Vec<String>, String> {
+    DDragon::get_versions(&http).await
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon, http))]
+pub async fn diff_versions(
+    ddragon: tauri::State<'_, DDragon>,
+    http: tauri::State<'_, HttpClient>,
+    old: String,
+    new: String,
+) -> Result<VersionDiff, String> {
+    ddragon.diff_versions(&http, &old, &new).await
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn get_augments(ddragon: tauri::State<'_, DDragon>) -> Result<Vec<Augment>, String> {
+    Ok(ddragon.get_augments())
+}
+
+#[tauri::command]
+#[instrument(skip(ddragon))]
+pub async fn search_champions(
+    ddragon: tauri::State<'_, DDragon>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<ChampionMatch>, String> {
+    Ok(ddragon.search_champions(&query, limit.unwrap_or(10)))
+}
+
+/// Returns the id of the added page.
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
 pub async fn add_champion_custom_rune(
     custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
     champion_id: String,
     game_mode: String,
     rune_item: LolRuneItem,
-) -> Result<bool, String> {
-    Ok(custom
-        .add_champion_rune(&champion_id, GameMode::from_str(&game_mode), rune_item)
-        .await)
+) -> Result<u32, String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    ddragon.validate_rune(&RuneItem::try_from(rune_item.clone())?)?;
+    custom
+        .add_champion_rune(champion.id(), GameMode::from_str(&game_mode), rune_item)
+        .await
 }
 
 #[tauri::command]
-#[instrument]
+#[instrument(skip(custom, ddragon))]
+pub async fn get_custom_build(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+) -> Result<CustomBuild, String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .get_build(champion.id(), GameMode::from_str(&game_mode))
+        .await
+}
+
+/// Save the whole custom build, refused if any rune page, spells or lane is invalid.
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn save_custom_build(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+    build: CustomBuild,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    let mode = GameMode::from_str(&game_mode);
+    validate_custom_build(&ddragon, &build, mode)?;
+    custom.save_build(champion.id(), mode, &build).await
+}
+
+/// Lanes must be known lanes and matchups ddragon ids of champions.
+fn validate_tags(ddragon: &DDragon, lanes: &[String], matchups: &[String]) -> Result<(), String> {
+    for lane in lanes {
+        Lane::from_str(lane)?;
+    }
+    for matchup in matchups {
+        if ddragon.resolve_champion(matchup)?.id() != matchup {
+            return Err(format!("Matchup {} is not a champion id", matchup));
+        }
+    }
+    Ok(())
+}
+
+fn validate_custom_build(ddragon: &DDragon, build: &CustomBuild, mode: GameMode) -> Result<(), String> {
+    for (i, page) in build.pages.iter().enumerate() {
+        let name = &page.page.name;
+        if build.pages[..i].iter().any(|p| p.id == page.id || &p.page.name == name) {
+            return Err(format!("Rune {} is saved twice", name));
+        }
+        ddragon
+            .validate_rune(&RuneItem::try_from(page.page.clone())?)
+            .map_err(|err| format!("Rune {}: {}", name, err))?;
+        validate_tags(ddragon, &page.lanes, &page.matchups)
+            .map_err(|err| format!("Rune {}: {}", name, err))?;
+    }
+    for spell in build.spell_items() {
+        ddragon.validate_spells(&spell, mode)?;
+    }
+    for lane in &build.lanes {
+        Lane::from_str(lane)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn export_custom_build_code(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+) -> Result<String, String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .export_code(champion.id(), GameMode::from_str(&game_mode))
+        .await
+}
+
+/// Write all custom builds to one file, returns how many were written.
+#[tauri::command]
+#[instrument(skip(custom))]
+pub async fn export_custom_builds_archive(
+    custom: tauri::State<'_, CustomProvider>,
+    path: String,
+) -> Result<usize, String> {
+    custom.export_archive(Path::new(&path)).await
+}
+
+/// Builds of `source` that can be imported, the rest is in the report.
+async fn load_import(
+    ddragon: &DDragon,
+    source: &ImportSource,
+) -> Result<(Vec<(GameMode, SharedBuild)>, ImportReport), String> {
+    let mut report = ImportReport::default();
+    let mut builds = Vec::new();
+    for mut shared in source.load().await? {
+        let mode = GameMode::from_str(&shared.mode);
+        let checked = ddragon.resolve_champion(&shared.champion).and_then(|champion| {
+            validate_custom_build(ddragon, &shared.build, mode)?;
+            Ok(champion)
+        });
+        match checked {
+            Ok(champion) => {
+                shared.champion = champion.id().to_string();
+                builds.push((mode, shared));
+            }
+            Err(err) => report
+                .rejected
+                .push(format!("{} {}: {}", shared.champion, shared.mode, err)),
+        }
+    }
+    Ok((builds, report))
+}
+
+/// Rune pages of `source` whose names are taken, to ask what to do before importing.
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn preview_custom_builds_import(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    source: ImportSource,
+) -> Result<Vec<ImportConflict>, String> {
+    let (builds, _) = load_import(&ddragon, &source).await?;
+    custom.import_conflicts(&builds).await
+}
+
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn import_custom_builds(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    source: ImportSource,
+    conflict: Conflict,
+) -> Result<ImportReport, String> {
+    let (builds, report) = load_import(&ddragon, &source).await?;
+    Ok(custom.import(builds, conflict, report).await)
+}
+
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
 pub async fn remove_champion_custom_rune(
     custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
     champion_id: String,
     game_mode: String,
     rune_name: String,
-) -> Result<bool, String> {
-    Ok(custom
-        .remove_champion_rune(&champion_id, GameMode::from_str(&game_mode), &rune_name)
-        .await)
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .remove_champion_rune(champion.id(), GameMode::from_str(&game_mode), &rune_name)
+        .await
 }
 
 #[tauri::command]
-#[instrument]
+#[instrument(skip(custom, ddragon))]
 pub async fn remove_champion_custom_runes(
     custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
     champion_id: String,
     game_mode: String,
-) -> Result<bool, String> {
-    Ok(custom
-        .remove_champion_runes(&champion_id, GameMode::from_str(&game_mode))
-        .await)
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .remove_champion_runes(champion.id(), GameMode::from_str(&game_mode))
+        .await
 }
 
 #[tauri::command]
@@ -347,15 +835,27 @@ pub async fn set_app_config(
 }
 
 #[tauri::command]
-#[instrument]
+#[instrument(skip(provider, ddragon, custom))]
 pub async fn save_app_config(
+    app: AppHandle,
     config: tauri::State<'_, Mutex<AppConfig>>,
-    provider: tauri::State<'_, Mutex<DynSource>>,
+    provider: tauri::State<'_, DynSource>,
+    http: tauri::State<'_, HttpClient>,
+    ddragon: tauri::State<'_, DDragon>,
+    custom: tauri::State<'_, CustomProvider>,
 ) -> Result<(), String> {
+    // Before saving, so a directory that can't be used is not kept.
+    let dir = config.lock().await.custom_builds_dir();
+    if custom.set_dir(dir).await? {
+        // No list means every build may have changed.
+        app.emit_all("custom_builds_changed", Vec::<CustomBuildChange>::new())
+            .unwrap();
+    }
     config.lock().await.save_to_local().await;
-    let mut provider = &mut *(provider.lock().await);
-    config.lock().await.invoke(&mut provider).await;
-    Ok(())
+    config.lock().await.invoke(&provider, &http).await?;
+    ddragon.set_routing(config.lock().await.static_data_routing());
+    let language = config.lock().await.language();
+    apply_language(&app, language).await
 }
 
 #[tauri::command]
@@ -363,3 +863,225 @@ pub async fn save_app_config(
 pub async fn log(msg: String) {
     info!(msg);
 }
+
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn update_custom_rune_page(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+    page_id: u32,
+    rune_item: LolRuneItem,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    ddragon.validate_rune(&RuneItem::try_from(rune_item.clone())?)?;
+    custom
+        .update_page(champion.id(), GameMode::from_str(&game_mode), page_id, rune_item)
+        .await
+}
+
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn rename_custom_rune_page(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+    page_id: u32,
+    name: String,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .rename_page(champion.id(), GameMode::from_str(&game_mode), page_id, &name)
+        .await
+}
+
+/// `page_ids` is every page id of the build, in the new order.
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn reorder_custom_rune_pages(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+    page_ids: Vec<u32>,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .reorder_pages(champion.id(), GameMode::from_str(&game_mode), &page_ids)
+        .await
+}
+
+/// Matchups may be any name of a champion, they are saved as ddragon ids.
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn tag_custom_rune_page(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+    page_id: u32,
+    lanes: Vec<String>,
+    matchups: Vec<String>,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    let matchups = matchups
+        .iter()
+        .map(|m| ddragon.resolve_champion(m).map(|c| c.id().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    validate_tags(&ddragon, &lanes, &matchups)?;
+    custom
+        .tag_page(champion.id(), GameMode::from_str(&game_mode), page_id, lanes, matchups)
+        .await
+}
+
+/// Called when a custom page is applied in the client.
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn mark_custom_rune_page_used(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+    page_id: u32,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .touch_page(champion.id(), GameMode::from_str(&game_mode), page_id)
+        .await
+}
+
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn remove_custom_rune_page(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    champion_id: String,
+    game_mode: String,
+    page_id: u32,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    custom
+        .remove_page(champion.id(), GameMode::from_str(&game_mode), page_id)
+        .await
+}
+
+/// Tell the window which custom builds look different after a template changed.
+fn emit_custom_builds_changed(app: &AppHandle, changes: Vec<CustomBuildChange>) {
+    if !changes.is_empty() {
+        app.emit_all("custom_builds_changed", changes).unwrap();
+    }
+}
+
+#[tauri::command]
+#[instrument(skip(custom))]
+pub async fn get_rune_templates(
+    custom: tauri::State<'_, CustomProvider>,
+) -> Result<Vec<RuneTemplate>, String> {
+    Ok(custom.get_templates().await?.templates)
+}
+
+/// Returns the id of the added template.
+#[tauri::command]
+#[instrument(skip(custom, ddragon))]
+pub async fn add_rune_template(
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    rune_item: LolRuneItem,
+) -> Result<u32, String> {
+    ddragon.validate_rune(&RuneItem::try_from(rune_item.clone())?)?;
+    custom.add_template(rune_item).await
+}
+
+#[tauri::command]
+#[instrument(skip(app, custom, ddragon))]
+pub async fn update_rune_template(
+    app: AppHandle,
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    template_id: u32,
+    rune_item: LolRuneItem,
+) -> Result<(), String> {
+    let changes = custom
+        .update_template(template_id, rune_item, |page| {
+            ddragon.validate_rune(&RuneItem::try_from(page.clone())?)
+        })
+        .await?;
+    emit_custom_builds_changed(&app, changes);
+    Ok(())
+}
+
+#[tauri::command]
+#[instrument(skip(app, custom))]
+pub async fn remove_rune_template(
+    app: AppHandle,
+    custom: tauri::State<'_, CustomProvider>,
+    template_id: u32,
+) -> Result<(), String> {
+    let changes = custom.remove_template(template_id).await?;
+    emit_custom_builds_changed(&app, changes);
+    Ok(())
+}
+
+#[tauri::command]
+#[instrument(skip(app, custom, ddragon))]
+pub async fn link_rune_template(
+    app: AppHandle,
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    template_id: u32,
+    champion_id: String,
+    game_mode: String,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    let changes = custom
+        .link_template(template_id, champion.id(), GameMode::from_str(&game_mode))
+        .await?;
+    emit_custom_builds_changed(&app, changes);
+    Ok(())
+}
+
+#[tauri::command]
+#[instrument(skip(app, custom, ddragon))]
+pub async fn unlink_rune_template(
+    app: AppHandle,
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    template_id: u32,
+    champion_id: String,
+    game_mode: String,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    let changes = custom
+        .unlink_template(template_id, champion.id(), GameMode::from_str(&game_mode))
+        .await?;
+    emit_custom_builds_changed(&app, changes);
+    Ok(())
+}
+
+/// Refused if the template with the overrides is not a valid page.
+#[tauri::command]
+#[instrument(skip(app, custom, ddragon))]
+pub async fn set_rune_template_override(
+    app: AppHandle,
+    custom: tauri::State<'_, CustomProvider>,
+    ddragon: tauri::State<'_, DDragon>,
+    template_id: u32,
+    champion_id: String,
+    game_mode: String,
+    overrides: TemplateOverride,
+) -> Result<(), String> {
+    let champion = ddragon.resolve_champion(&champion_id)?;
+    let changes = custom
+        .set_template_override(
+            template_id,
+            champion.id(),
+            GameMode::from_str(&game_mode),
+            overrides,
+            |page| ddragon.validate_rune(&RuneItem::try_from(page.clone())?),
+        )
+        .await?;
+    emit_custom_builds_changed(&app, changes);
+    Ok(())
+}
@@ -0,0 +1,304 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use futures_util::lock::Mutex;
+use reqwest::{
+    header::{HeaderMap, HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RETRY_AFTER},
+    Certificate, Client, Proxy, Response, StatusCode, Url,
+};
+use serde::de::DeserializeOwned;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+/// Bytes of response bodies kept for conditional requests.
+const CONDITIONAL_CACHE_SIZE: usize = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    pub timeout: Duration,
+    /// Retries after the first attempt, for transient errors only.
+    pub max_retries: u32,
+    /// Requests started per second at most, 0 is no limit.
+    pub requests_per_second: u32,
+    /// `http://`, `https://` or `socks5://` url, the system proxy is used if `None`.
+    pub proxy: Option<String>,
+    /// Hosts that are connected direct, a leading `.` or `*.` matches subdomains too.
+    pub no_proxy: Vec<String>,
+    /// PEM or DER files of certificates to trust besides the system ones.
+    pub ca_certs: Vec<PathBuf>,
+}
+
+/// Http client of all outbound requests (providers, ddragon), not for the lcu.
+/// Cheap to clone, clones share the settings, rate limit and conditional cache.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    client: RwLock<Client>,
+    options: RwLock<HttpOptions>,
+    next_request: Mutex<Instant>,
+    conditional: Mutex<ConditionalCache>,
+}
+
+#[derive(Debug, Clone)]
+struct Validated {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, Default)]
+struct ConditionalCache {
+    entries: HashMap<String, Validated>,
+    order: VecDeque<String>,
+    size: usize,
+}
+
+impl HttpClient {
+    /// A client with bad proxy or certificates falls back to direct connection,
+    /// call `configure` to get the error.
+    pub fn new(options: HttpOptions) -> Self {
+        let client = HttpClient::build_client(&options).unwrap_or_else(|err| {
+            error!("Create http client failed, ignore proxy and certificates: {}", err);
+            HttpClient::build_client(&HttpOptions {
+                proxy: None,
+                no_proxy: vec![],
+                ca_certs: vec![],
+                ..options.clone()
+            })
+            .expect("Create http client error!")
+        });
+        HttpClient {
+            inner: Arc::new(Inner {
+                client: RwLock::new(client),
+                options: RwLock::new(options),
+                next_request: Mutex::new(Instant::now()),
+                conditional: Mutex::new(ConditionalCache::default()),
+            }),
+        }
+    }
+
+    /// Nothing changes if the proxy or certificates are bad.
+    pub fn configure(&self, options: HttpOptions) -> Result<(), String> {
+        *self.inner.client.write().unwrap() = HttpClient::build_client(&options)?;
+        *self.inner.options.write().unwrap() = options;
+        Ok(())
+    }
+
+    fn build_client(options: &HttpOptions) -> Result<Client, String> {
+        let mut builder = Client::builder()
+            .timeout(options.timeout)
+            .user_agent(format!("Lola/{}", env!("CARGO_PKG_VERSION")));
+        if let Some(proxy) = &options.proxy {
+            let proxy_url =
+                Url::parse(proxy).map_err(|err| format!("Bad proxy {}: {}", proxy, err))?;
+            if !matches!(proxy_url.scheme(), "http" | "https" | "socks5" | "socks5h") {
+                return Err(format!("Unsupported proxy scheme: {}", proxy_url.scheme()));
+            }
+            let no_proxy = options.no_proxy.clone();
+            builder = builder.proxy(Proxy::custom(move |url| {
+                match url.host_str() {
+                    Some(host) if is_no_proxy(host, &no_proxy) => None,
+                    _ => Some(proxy_url.clone()),
+                }
+            }));
+        }
+        for path in &options.ca_certs {
+            for cert in load_certificates(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        builder
+            .build()
+            .map_err(|err| format!("Create http client error: {}", err))
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, String> {
+        let text = self.get_text(url).await?;
+        serde_json::from_str(&text).map_err(|err| format!("Parse json of {} error: {}", url, err))
+    }
+
+    /// Get the body of url, retrying transient errors with exponential backoff.
+    /// An unchanged page that was got before is answered from memory.
+    pub async fn get_text(&self, url: &str) -> Result<String, String> {
+        let validated = self.inner.conditional.lock().await.entries.get(url).cloned();
+        match self.send(url, validated.as_ref()).await? {
+            Some(resp) => {
+                let headers = resp.headers().clone();
+                let body = resp
+                    .text()
+                    .await
+                    .map_err(|err| format!("Get body of {} error: {}", url, err))?;
+                self.remember(url, &headers, &body).await;
+                Ok(body)
+            }
+            None => {
+                info!("{} is not modified.", url);
+                Ok(validated.unwrap().body)
+            }
+        }
+    }
+
+    /// Get a binary body, like an image. Retried like `get_text` but never conditional.
+    pub async fn get_bytes(&self, url: &str) -> Result<Vec<u8>, String> {
+        let resp = self
+            .send(url, None)
+            .await?
+            .ok_or(format!("{} is not modified.", url))?;
+        resp.bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(|err| format!("Get body of {} error: {}", url, err))
+    }
+
+    /// Successful response of url, `None` if it is not modified since `validated`.
+    async fn send(
+        &self,
+        url: &str,
+        validated: Option<&Validated>,
+    ) -> Result<Option<Response>, String> {
+        let client = self.inner.client.read().unwrap().clone();
+        let max_retries = self.inner.options.read().unwrap().max_retries;
+
+        let mut attempt = 0;
+        loop {
+            self.wait_turn().await;
+            let mut request = client.get(url);
+            if let Some(validated) = validated {
+                if let Some(etag) = &validated.etag {
+                    request = request.header(IF_NONE_MATCH, etag);
+                }
+                if let Some(last_modified) = &validated.last_modified {
+                    request = request.header(IF_MODIFIED_SINCE, last_modified);
+                }
+            }
+
+            let retry_after = match request.send().await {
+                Ok(resp) if resp.status() == StatusCode::NOT_MODIFIED && validated.is_some() => {
+                    return Ok(None);
+                }
+                Ok(resp) if resp.status().is_success() => return Ok(Some(resp)),
+                Ok(resp) if is_transient_status(resp.status()) => {
+                    warn!("{} returned {}", url, resp.status());
+                    parse_retry_after(resp.headers())
+                }
+                Ok(resp) => return Err(format!("{} returned {}", url, resp.status())),
+                Err(err) if err.is_timeout() || err.is_connect() || err.is_request() => {
+                    warn!("Request {} error: {}", url, err);
+                    None
+                }
+                Err(err) => return Err(format!("Request {} error: {}", url, err)),
+            };
+
+            if attempt >= max_retries {
+                return Err(format!("Request {} failed after {} attempts.", url, attempt + 1));
+            }
+            let backoff = Duration::from_millis(500 * 2u64.pow(attempt)).min(Duration::from_secs(30));
+            tokio::time::sleep(retry_after.unwrap_or(backoff)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Wait until the rate limit allows another request.
+    async fn wait_turn(&self) {
+        let requests_per_second = self.inner.options.read().unwrap().requests_per_second;
+        if requests_per_second == 0 {
+            return;
+        }
+        let interval = Duration::from_secs(1) / requests_per_second;
+        let mut next = self.inner.next_request.lock().await;
+        tokio::time::sleep_until(*next).await;
+        *next = Instant::now() + interval;
+    }
+
+    async fn remember(&self, url: &str, headers: &HeaderMap, body: &str) {
+        let header = |name: HeaderName| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_owned)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        if (etag.is_none() && last_modified.is_none()) || body.len() > CONDITIONAL_CACHE_SIZE / 4 {
+            return;
+        }
+
+        let mut cache = self.inner.conditional.lock().await;
+        if let Some(old) = cache.entries.remove(url) {
+            cache.size -= old.body.len();
+            cache.order.retain(|u| u != url);
+        }
+        while cache.size + body.len() > CONDITIONAL_CACHE_SIZE {
+            match cache.order.pop_front() {
+                Some(oldest) => {
+                    if let Some(old) = cache.entries.remove(&oldest) {
+                        cache.size -= old.body.len();
+                    }
+                }
+                None => break,
+            }
+        }
+        cache.size += body.len();
+        cache.order.push_back(url.to_owned());
+        cache.entries.insert(
+            url.to_owned(),
+            Validated {
+                etag,
+                last_modified,
+                body: body.to_owned(),
+            },
+        );
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// `Retry-After` in seconds, dates are ignored.
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(|secs: u64| Duration::from_secs(secs.min(60)))
+}
+
+fn is_no_proxy(host: &str, no_proxy: &[String]) -> bool {
+    no_proxy.iter().any(|pattern| {
+        let pattern = pattern.trim();
+        if pattern == "*" {
+            return true;
+        }
+        let domain = pattern.trim_start_matches('*').trim_start_matches('.');
+        !domain.is_empty()
+            && (host.eq_ignore_ascii_case(domain)
+                || (pattern.starts_with(['.', '*'])
+                    && host.to_lowercase().ends_with(&format!(".{}", domain.to_lowercase()))))
+    })
+}
+
+/// All certificates of a PEM bundle, or the one of a DER file.
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>, String> {
+    let bytes = std::fs::read(path).map_err(|err| format!("Read {:?} error: {}", path, err))?;
+    let bad_cert = |err: reqwest::Error| format!("Bad certificate {:?}: {}", path, err);
+    match std::str::from_utf8(&bytes) {
+        Ok(pem) if pem.contains("-----BEGIN CERTIFICATE-----") => pem
+            .split_inclusive("-----END CERTIFICATE-----")
+            .filter(|block| block.contains("-----BEGIN CERTIFICATE-----"))
+            .map(|block| Certificate::from_pem(block.as_bytes()).map_err(bad_cert))
+            .collect(),
+        _ => Ok(vec![Certificate::from_der(&bytes).map_err(bad_cert)?]),
+    }
+}
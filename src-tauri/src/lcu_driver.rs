@@ -12,7 +12,10 @@ use reqwest::{
 };
 use tokio::sync::mpsc::UnboundedReceiver;
 
-use crate::ws::{OnJsonApiEventData, WSClient};
+use crate::{
+    ddragon::patch_of,
+    ws::{OnJsonApiEventData, WSClient},
+};
 
 #[derive(Debug)]
 pub struct LcuDriver {
@@ -152,6 +155,30 @@ impl LcuDriver {
         }
     }
 
+    /// Patch of the client, like `13.5`.
+    pub async fn get_game_patch(&self) -> Result<String, String> {
+        let version = self
+            .get("/lol-patch/v1/game-version")
+            .await
+            .map_err(|err| err.to_string())?;
+        match version.as_str() {
+            Some(version) => Ok(patch_of(version)),
+            None => Err(format!("Unknown game version {}", version)),
+        }
+    }
+
+    /// Locale of the client, like `zh_CN`.
+    pub async fn get_locale(&self) -> Result<String, String> {
+        let region = self
+            .get("/riotclient/region-locale")
+            .await
+            .map_err(|err| err.to_string())?;
+        match region["locale"].as_str() {
+            Some(locale) => Ok(locale.to_owned()),
+            None => Err(format!("Unknown region locale {}", region)),
+        }
+    }
+
     pub async fn hook_on_window(&self, handle: tauri::AppHandle) {
         let ws_rx = self.ws_rx.clone();
         tokio::spawn(async move {
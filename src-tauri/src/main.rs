@@ -15,15 +15,24 @@ use window_shadows::set_shadow;
 use crate::{
     app_config::AppConfig,
     app_status::AppStatus,
+    cache_warmer::CacheWarmer,
     ddragon::{DDragon, Language}, source_provider::{opgg::OPGG, DynSource}, custom_provider::CustomProvider,
+    http_client::HttpClient,
+    asset_cache::{AssetCache, ASSET_SCHEME},
 };
 
 mod app_config;
 mod app_status;
+mod asset_cache;
+mod champion_id;
+mod champion_search;
+mod cache_warmer;
 mod ddragon;
 mod for_render;
+mod http_client;
 mod lcu_driver;
 mod source_provider;
+mod static_data;
 mod ws;
 mod tray;
 mod custom_provider;
@@ -64,18 +73,36 @@ fn main() {
             watch_config = true;
         }
     };
-    let custom = CustomProvider::initialize();
-    let ddragon = block_on(DDragon::new(Language::en_US));
-    let mut opgg: DynSource = Box::new(block_on(OPGG::new()));
     let config = block_on(AppConfig::from_local());
-    block_on(config.invoke(&mut opgg));
+    let custom = CustomProvider::initialize(config.custom_builds_dir())
+        .expect("Can't create the custom builds directory.");
+    let http = HttpClient::new(config.http_options());
+    // Following the client starts in english until the client is connected.
+    let ddragon = block_on(DDragon::new(
+        config.language().unwrap_or(Language::en_US),
+        config.static_data_routing(),
+    ));
+    let opgg: DynSource =
+        Arc::new(block_on(OPGG::new(http.clone())).expect("Can't create the build cache."));
+    if let Err(err) = block_on(config.invoke(&opgg, &http)) {
+        error!("Apply http config failed: {}", err);
+    }
+    block_on(opgg.set_patch(&ddragon.get_patch()));
+    let assets = AssetCache::initialize(http.clone());
+    block_on(assets.prune(&ddragon.get_version()));
     let config = Mutex::new(config);
     let app = builder
         .manage(ddragon)
-        .manage(Mutex::new(opgg))
+        .manage(opgg)
+        .manage(http)
         .manage(app_status.clone())
         .manage(config)
         .manage(custom)
+        .manage(CacheWarmer::initialize())
+        .manage(assets)
+        .register_uri_scheme_protocol(ASSET_SCHEME, |app, request| {
+            app.state::<AssetCache>().handle(request)
+        })
         .system_tray(tray::create_tray())
         .on_system_tray_event(tray::handle_event)
         .invoke_handler(tauri::generate_handler![
@@ -94,15 +121,58 @@ fn main() {
             for_render::get_champion_raw_info,
             for_render::get_ddragon_version,
             for_render::get_champion_icon,
+            for_render::get_languages,
+            for_render::get_rune_infos,
+            for_render::get_rune_trees,
+            for_render::get_spell_infos,
+            for_render::get_item_infos,
+            for_render::get_item,
+            for_render::get_summoner_spell,
+            for_render::get_maps,
+            for_render::get_queues,
+            for_render::get_queue,
+            for_render::search_champions,
+            for_render::get_ddragon_versions,
+            for_render::diff_versions,
+            for_render::get_augments,
+            for_render::get_custom_build,
+            for_render::save_custom_build,
+            for_render::export_custom_build_code,
+            for_render::export_custom_builds_archive,
+            for_render::preview_custom_builds_import,
+            for_render::import_custom_builds,
+            for_render::update_custom_rune_page,
+            for_render::rename_custom_rune_page,
+            for_render::reorder_custom_rune_pages,
+            for_render::tag_custom_rune_page,
+            for_render::mark_custom_rune_page_used,
+            for_render::remove_custom_rune_page,
+            for_render::get_rune_templates,
+            for_render::add_rune_template,
+            for_render::update_rune_template,
+            for_render::remove_rune_template,
+            for_render::link_rune_template,
+            for_render::unlink_rune_template,
+            for_render::set_rune_template_override,
             for_render::get_app_config,
             for_render::set_app_config,
             for_render::save_app_config,
             for_render::clear_cache,
+            for_render::cache_stats,
+            for_render::list_cache_entries,
+            for_render::evict_cache_entry,
+            for_render::start_cache_warmer,
+            for_render::stop_cache_warmer,
+            for_render::get_cache_warmer_progress,
             for_render::log,
             for_render::show_in_folder,
         ])
         .build(tauri::generate_context!())
         .expect("Can't run tauri application.");
+    tauri::async_runtime::spawn(for_render::emit_build_updates(app.handle()));
+    tauri::async_runtime::spawn(for_render::emit_schema_drift(app.handle()));
+    tauri::async_runtime::spawn(for_render::refresh_ddragon(app.handle()));
+    tauri::async_runtime::spawn(for_render::emit_custom_build_changes(app.handle()));
 
     if watch_config {
         let a = app_status.clone();
@@ -1,31 +1,74 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use self::{
+    cache_manager::{CacheEntryInfo, CacheStats},
+    schema::DriftReport,
+};
+use crate::champion_id::ChampionId;
+
+use std::sync::Arc;
 
 pub mod cache_manager;
+pub mod in_flight;
 pub mod opgg;
+pub mod schema;
 
 pub type DynChampionInfo = Box<dyn ChampionInfo>;
-pub type DynSource = Box<dyn Source>;
+pub type DynSource = Arc<dyn Source>;
 
 #[async_trait]
 pub trait Source: Send + Sync {
-    fn set_fetch_mode(&mut self, mode: FetchMode);
+    fn set_fetch_mode(&self, mode: FetchMode);
     async fn set_expired(&self, days: i64);
     async fn get_expired(&self) -> i64;
+    /// Set the current game patch, cache of older patches is treated as expired.
+    async fn set_patch(&self, patch: &str);
     fn fetch_mode(&self) -> FetchMode;
     async fn get_champion_info(
         &self,
-        champion_id: &str,
+        champion: &ChampionId,
         lane: Lane,
         mode: GameMode,
     ) -> Result<DynChampionInfo, String>;
-    async fn clear_cache(&mut self);
+    /// Builds refreshed in background that changed, see `FetchMode::StaleWhileRevalidate`.
+    fn subscribe_updates(&self) -> broadcast::Receiver<BuildUpdated>;
+    /// Payloads that no longer match what the provider is expected to send.
+    fn subscribe_drift(&self) -> broadcast::Receiver<DriftReport>;
+    /// Download and cache the build unless a fresh copy is cached already,
+    /// no matter the fetch mode. Return `true` if it was downloaded.
+    async fn warm_cache(&self, champion: &ChampionId, lane: Lane, mode: GameMode)
+        -> Result<bool, String>;
+    async fn clear_cache(&self);
+    async fn set_cache_size_limit(&self, bytes: u64);
+    async fn cache_stats(&self) -> Result<CacheStats, String>;
+    async fn list_cache_entries(&self) -> Result<Vec<CacheEntryInfo>, String>;
+    async fn evict_cache_entry(
+        &self,
+        champion_id: &str,
+        lane: Lane,
+        mode: GameMode,
+    ) -> Result<bool, String>;
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
 pub enum FetchMode {
+    /// Always download, no cache at all.
     Online,
+    /// Use cache until it is expired, or the download failed.
     Auto,
+    /// Only use cache.
+    Offline,
+    /// Use cache at once and refresh expired one in background.
+    StaleWhileRevalidate,
+}
+
+#[derive(Debug, Clone)]
+pub struct BuildUpdated {
+    pub champion: ChampionId,
+    pub lane: Lane,
+    pub mode: GameMode,
 }
 #[async_trait]
 pub trait ChampionInfo: Send + Sync {
@@ -33,9 +76,11 @@ pub trait ChampionInfo: Send + Sync {
     async fn get_runes(&self) -> Result<Vec<RuneItem>, String>;
     async fn get_spells(&self) -> Result<Vec<SpellItem>, String>;
     fn get_lane(&self) -> Lane;
+    /// Game patch that the data is fetched for.
+    fn get_patch(&self) -> String;
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Lane {
     Top,
     Mid,
@@ -44,7 +89,7 @@ pub enum Lane {
     Jungle,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GameMode {
     Classic,
     Aram,
@@ -58,6 +103,28 @@ pub struct Build {
     pub is_cache: bool,
     pub is_custom: bool,
     pub lane: String,
+    /// Game patch of the build, empty for custom builds.
+    pub patch: String,
+    /// Why runes or spells of the build were dropped, invalid entries are never shown.
+    #[serde(default)]
+    pub rejected: Vec<String>,
+    /// Static data was not loaded, so runes and spells were not checked.
+    #[serde(default)]
+    pub unchecked: bool,
+    /// Only custom builds have the following.
+    #[serde(default)]
+    pub items: Vec<ItemBlock>,
+    #[serde(default)]
+    pub skill_order: Vec<Skill>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// Build data a provider fetched, it is what the cache keeps.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildRecord {
+    pub runes: Vec<RuneItem>,
+    pub spells: Vec<SpellItem>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -74,6 +141,9 @@ pub struct RuneItem {
 
     #[serde(skip_deserializing)]
     pub name: String,
+    /// Set on custom runes that come from a rune template.
+    #[serde(default)]
+    pub template_id: Option<u32>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -94,6 +164,21 @@ pub struct LolRuneItem {
     pub sub_style_id: i32,
 }
 
+/// Items to buy together, like the starting items.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemBlock {
+    pub name: String,
+    pub items: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Skill {
+    Q,
+    W,
+    E,
+    R,
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SpellItem {
     pub ids: Vec<i32>,
@@ -156,9 +241,19 @@ impl ToString for GameMode {
     }
 }
 
-impl From<LolRuneItem> for RuneItem {
-    fn from(l: LolRuneItem) -> Self {
-        RuneItem {
+impl TryFrom<LolRuneItem> for RuneItem {
+    type Error = String;
+
+    /// A full page has 4 primary runes, 2 secondary runes and 3 stat shards.
+    fn try_from(l: LolRuneItem) -> Result<Self, Self::Error> {
+        if l.selected_perk_ids.len() != 9 {
+            return Err(format!(
+                "Rune page {} has {} perks instead of 9",
+                l.name,
+                l.selected_perk_ids.len()
+            ));
+        }
+        Ok(RuneItem {
             id: l.id,
             primary_page_id: l.primary_style_id,
             primary_rune_ids: l.selected_perk_ids[0..4].to_vec(),
@@ -168,7 +263,8 @@ impl From<LolRuneItem> for RuneItem {
             play: 0,
             win: 0,
             pick_rate: 0.0,
-            name: l.name
-        }
+            name: l.name,
+            template_id: None,
+        })
     }
 }
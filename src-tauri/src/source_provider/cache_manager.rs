@@ -1,43 +1,65 @@
-use std::{path::{PathBuf}, fs};
+use std::{fs, path::Path};
 
-use chrono::{DateTime, Utc, Duration};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use directories::ProjectDirs;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
-use tokio::{fs::File, io::{AsyncReadExt, AsyncWriteExt}};
-use tracing::{info, error};
+use tracing::{error, info};
 
-use crate::util::write_to_file;
+use crate::ddragon::is_newer_patch;
 
 use super::{GameMode, Lane};
 
-#[derive(Debug, Clone)]
+/// Bump it when the table layout changes, old caches are dropped then.
+const SCHEMA_VERSION: i32 = 4;
+
+#[derive(Debug)]
 pub struct CacheManager {
-    cache_dir: PathBuf,
-    json: CacheLogJson,
+    provider: String,
+    db: Connection,
     expired: i64,
+    /// Game patch that new data is fetched for, data of older patches is old.
+    patch: String,
+    /// Bytes of data to keep at most, least recently used entries go first.
+    size_limit: u64,
 }
 
-impl CacheManager {
-    pub fn new(cache_dir: PathBuf) -> Self {
-        CacheManager {
-            cache_dir,
-            json: CacheLogJson::new(),
-            expired: 3
-        }
-    }
+#[derive(Debug, Clone)]
+pub struct CacheData {
+    /// Json of `BuildRecord`.
+    pub data: String,
+    pub patch: String,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct CacheLogJson {
-    pub champions: Vec<ChampionCacheInfo>,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub entries: u64,
+    pub size: u64,
+    pub size_limit: u64,
 }
 
-impl CacheLogJson {
-    pub fn new() -> Self {
-        CacheLogJson {
-            champions: Vec::new(),
-        }
-    }
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntryInfo {
+    pub provider: String,
+    pub champion: String,
+    pub lane: String,
+    pub mode: String,
+    pub query: String,
+    pub patch: String,
+    pub size: u64,
+    pub last_modified: DateTime<Utc>,
+    pub last_access: DateTime<Utc>,
+}
+
+/// Every cached build is stored under this key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub provider: String,
+    pub champion: String,
+    pub lane: Lane,
+    pub mode: GameMode,
+    /// Extra parameters of the request, like tier or region. Empty if no.
+    pub query: String,
 }
 
 impl CacheManager {
@@ -45,161 +67,341 @@ impl CacheManager {
         self.expired = days;
     }
 
-    pub fn get_expired(&self)-> i64 {
+    pub fn get_expired(&self) -> i64 {
         self.expired
     }
 
-    pub async fn initialize() -> Result<Self, String> {
+    /// Only move to newer patch, the client and ddragon may disagree for a while.
+    pub fn set_patch(&mut self, patch: &str) {
+        if self.patch.is_empty() || is_newer_patch(patch, &self.patch) {
+            info!("Cache patch is {} now.", patch);
+            self.patch = patch.to_owned();
+        }
+    }
+
+    pub fn get_patch(&self) -> String {
+        self.patch.clone()
+    }
+
+    pub fn set_size_limit(&mut self, bytes: u64) -> Result<(), String> {
+        self.size_limit = bytes;
+        self.evict_over_limit()
+    }
+
+    pub fn initialize(provider: &str) -> Result<Self, String> {
         if let Some(proj) = ProjectDirs::from("com", "Jinte", "Lola") {
-            let cache_dir = proj.cache_dir().join("opgg");
-            fs::create_dir_all(&cache_dir).unwrap();
-
-            let cachelog = cache_dir.join("cache.log");
-            if cachelog.is_file() {
-                let file = File::open(cachelog).await;
-                match file {
-                    Ok(mut file) => {
-                        let mut buff = String::new();
-                        file.read_to_string(&mut buff)
-                            .await
-                            .map_err(|err| err.to_string())?;
-                        let json: CacheLogJson =
-                            serde_json::from_str(&buff).map_err(|err| err.to_string())?;
-
-                        return Ok(CacheManager { cache_dir, json, expired: 3 });
-                    }
-                    Err(err) => {
-                        error!("{:?}", err);
-                        return Err(err.to_string());
-                    }
-                }
-            } else {
-                let file = File::create(cachelog).await;
-                match file {
-                    Ok(mut file) => {
-                        let cl = CacheManager::new(cache_dir);
-                        file.write(serde_json::to_string(&cl.json).unwrap().as_bytes())
-                            .await
-                            .map_err(|err| err.to_string())?;
-                        return Ok(cl);
-                    }
-                    Err(err) => return Err(err.to_string()),
+            fs::create_dir_all(proj.cache_dir()).map_err(|err| err.to_string())?;
+            remove_legacy_cache(&proj.cache_dir().join(provider));
+
+            let db_path = proj.cache_dir().join("cache.db");
+            let db = match open_db(&db_path) {
+                Ok(db) => db,
+                Err(err) => {
+                    error!("Cache database is broken, recreate it: {}", err);
+                    let broken = db_path.with_extension("db.broken");
+                    fs::rename(&db_path, broken).map_err(|err| err.to_string())?;
+                    open_db(&db_path)?
                 }
-            }
+            };
+            Ok(CacheManager {
+                provider: provider.to_owned(),
+                db,
+                expired: 3,
+                patch: String::new(),
+                size_limit: 200 * 1024 * 1024,
+            })
         } else {
             Err(String::from("Can't get cache directory path!"))
         }
     }
-    pub fn is_champion_data_old(&self, champion_name: &str, lane: Lane, mode: GameMode) -> bool {
-        for champ in &self.json.champions {
-            if champ.is_same(champion_name, lane, mode) {
-                return champ.is_expired(Duration::days(self.expired));
-            }
-        }
-        info!("No found log, it is old data");
-        true
-    }
-
-    pub async fn update_champion(
-        &mut self,
-        champion_name: &str,
-        lane: Lane,
-        mode: GameMode,
-        data: &str,
-    ) -> Result<(), String> {
-        for champ in &mut self.json.champions {
-            if champ.is_same(champion_name, lane, mode) {
-                champ.update_last_modified(Utc::now());
-                write_to_file(&self.cache_dir.join(champ.get_file_name()), data).await?;
-                self.save_cache_log().await?;
-                return Ok(());
-            }
-        }
 
-        // No found it.
-        let new = ChampionCacheInfo::new(&champion_name, lane, mode);
-        write_to_file(&self.cache_dir.join(&new.get_file_name()), data).await?;
-        self.json.champions.push(new);
-        self.save_cache_log().await?;
+    /// A cache that is gone when the app quits, for when the cache file can't be used.
+    pub fn in_memory(provider: &str) -> Result<Self, String> {
+        Ok(CacheManager {
+            provider: provider.to_owned(),
+            // Sqlite keeps `:memory:` databases in memory.
+            db: open_db(Path::new(":memory:"))?,
+            expired: 3,
+            patch: String::new(),
+            size_limit: 200 * 1024 * 1024,
+        })
+    }
 
-        Ok(())
+    pub fn key(&self, champion_name: &str, lane: Lane, mode: GameMode) -> CacheKey {
+        CacheKey {
+            provider: self.provider.clone(),
+            champion: champion_name.to_owned(),
+            lane,
+            mode,
+            query: String::new(),
+        }
     }
 
-    pub async fn clear_cache(&mut self) {
-        self.json.champions.clear();
-        fs::remove_dir_all(&self.cache_dir).unwrap();
-        fs::create_dir_all(&self.cache_dir).unwrap();
-        self.json.champions.clear();
+    pub fn is_champion_data_old(&self, key: &CacheKey) -> bool {
+        let row: Option<(i64, String)> = self
+            .db
+            .query_row(
+                "SELECT last_modified, patch FROM builds
+                 WHERE provider = ?1 AND champion = ?2 AND lane = ?3 AND mode = ?4 AND query = ?5",
+                params![
+                    key.provider,
+                    key.champion,
+                    key.lane.to_string(),
+                    key.mode.to_string(),
+                    key.query
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .unwrap_or_else(|err| {
+                error!("Query cache failed: {}", err);
+                None
+            });
+        match row {
+            Some((last_modified, patch)) => {
+                if is_newer_patch(&self.patch, &patch) {
+                    info!("Cache is for patch {}, it is old data", patch);
+                    return true;
+                }
+                match Utc.timestamp_opt(last_modified, 0).single() {
+                    Some(last_modified) => {
+                        Utc::now().signed_duration_since(last_modified)
+                            >= Duration::days(self.expired)
+                    }
+                    None => {
+                        error!("Cache has a broken timestamp {}, it is old data", last_modified);
+                        true
+                    }
+                }
+            }
+            None => {
+                info!("No found cache, it is old data");
+                true
+            }
+        }
     }
 
-    pub async fn save_cache_log(&self) -> Result<(), String> {
-        let tar = self.cache_dir.join("cache.log");
+    /// Save data of current patch, return it with the patch stamped.
+    pub fn update_champion(&self, key: &CacheKey, data: String) -> Result<CacheData, String> {
+        let now = Utc::now().timestamp();
+        self.db
+            .execute(
+                "INSERT OR REPLACE INTO builds
+                 (provider, champion, lane, mode, query, data, last_modified, patch, size, last_access)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?7)",
+                params![
+                    key.provider,
+                    key.champion,
+                    key.lane.to_string(),
+                    key.mode.to_string(),
+                    key.query,
+                    data,
+                    now,
+                    self.patch,
+                    data.len() as i64
+                ],
+            )
+            .map_err(|err| err.to_string())?;
+        self.evict_over_limit()?;
+        Ok(CacheData {
+            data,
+            patch: self.patch.clone(),
+        })
+    }
 
-        write_to_file(&tar, &serde_json::to_string(&self.json).unwrap()).await?;
+    pub fn clear_cache(&self) -> Result<(), String> {
+        self.db
+            .execute(
+                "DELETE FROM builds WHERE provider = ?1",
+                params![self.provider],
+            )
+            .map_err(|err| err.to_string())?;
         Ok(())
     }
 
-    pub async fn get_champion_data(
-        &self,
-        champion_name: &str,
-        lane: Lane,
-        mode: GameMode,
-    ) -> Result<String, String> {
-        let champ_cache_file = self.cache_dir.join(ChampionCacheInfo::get_file_name_manual(
-            champion_name,
-            lane,
-            mode,
-        ));
-        if champ_cache_file.is_file() {
-            let file = File::open(champ_cache_file).await;
-            match file {
-                Ok(mut file) => {
-                    let mut next_data = String::new();
-                    file.read_to_string(&mut next_data)
-                        .await
-                        .map_err(|err| err.to_string())?;
-                    return Ok(next_data);
-                }
-                Err(err) => return Err(err.to_string()),
-            }
-        }
-        else {
-            Err(String::from("cache file no exists!"))
-        }
+    pub fn get_champion_data(&self, key: &CacheKey) -> Result<CacheData, String> {
+        self.db
+            .execute(
+                "UPDATE builds SET last_access = ?6
+                 WHERE provider = ?1 AND champion = ?2 AND lane = ?3 AND mode = ?4 AND query = ?5",
+                params![
+                    key.provider,
+                    key.champion,
+                    key.lane.to_string(),
+                    key.mode.to_string(),
+                    key.query,
+                    Utc::now().timestamp()
+                ],
+            )
+            .map_err(|err| err.to_string())?;
+        self.db
+            .query_row(
+                "SELECT data, patch FROM builds
+                 WHERE provider = ?1 AND champion = ?2 AND lane = ?3 AND mode = ?4 AND query = ?5",
+                params![
+                    key.provider,
+                    key.champion,
+                    key.lane.to_string(),
+                    key.mode.to_string(),
+                    key.query
+                ],
+                |row| {
+                    Ok(CacheData {
+                        data: row.get(0)?,
+                        patch: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|err| err.to_string())?
+            .ok_or(String::from("cache data no exists!"))
     }
-}
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct ChampionCacheInfo {
-    name_with_lane: String,
-    last_modified: DateTime<Utc>,
-}
+    /// Entries and size of this provider, what `clear_cache` empties.
+    pub fn stats(&self) -> Result<CacheStats, String> {
+        let (entries, size): (i64, i64) = self
+            .db
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM builds WHERE provider = ?1",
+                params![self.provider],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|err| err.to_string())?;
+        Ok(CacheStats {
+            entries: entries as u64,
+            size: size as u64,
+            size_limit: self.size_limit,
+        })
+    }
 
-impl ChampionCacheInfo {
-    pub fn new(name: &str, lane: Lane, mode: GameMode) -> Self {
-        ChampionCacheInfo {
-            name_with_lane: format!("{}-{:?}-{:?}", name, lane, mode),
-            last_modified: Utc::now(),
-        }
+    /// All entries, most recently used first.
+    pub fn list_entries(&self) -> Result<Vec<CacheEntryInfo>, String> {
+        let mut stmt = self
+            .db
+            .prepare(
+                "SELECT provider, champion, lane, mode, query, patch, size, last_modified, last_access
+                 FROM builds ORDER BY last_access DESC",
+            )
+            .map_err(|err| err.to_string())?;
+        let entries = stmt
+            .query_map([], |row| {
+                let last_modified = Utc.timestamp_opt(row.get(7)?, 0).single();
+                let last_access = Utc.timestamp_opt(row.get(8)?, 0).single();
+                let (last_modified, last_access) = match (last_modified, last_access) {
+                    (Some(last_modified), Some(last_access)) => (last_modified, last_access),
+                    // Rows with a broken timestamp are left out.
+                    _ => return Ok(None),
+                };
+                Ok(Some(CacheEntryInfo {
+                    provider: row.get(0)?,
+                    champion: row.get(1)?,
+                    lane: row.get(2)?,
+                    mode: row.get(3)?,
+                    query: row.get(4)?,
+                    patch: row.get(5)?,
+                    size: row.get::<_, i64>(6)? as u64,
+                    last_modified,
+                    last_access,
+                }))
+            })
+            .map_err(|err| err.to_string())?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| err.to_string())?;
+        Ok(entries.into_iter().flatten().collect())
     }
 
-    pub fn is_same(&self, name: &str, lane: Lane, mode: GameMode) -> bool {
-        self.name_with_lane == format!("{}-{:?}-{:?}", name, lane, mode)
+    /// Remove cached data of champion with any query, return `false` if no found.
+    pub fn evict(&self, champion_name: &str, lane: Lane, mode: GameMode) -> Result<bool, String> {
+        let removed = self
+            .db
+            .execute(
+                "DELETE FROM builds WHERE provider = ?1 AND champion = ?2 AND lane = ?3 AND mode = ?4",
+                params![
+                    self.provider,
+                    champion_name,
+                    lane.to_string(),
+                    mode.to_string()
+                ],
+            )
+            .map_err(|err| err.to_string())?;
+        Ok(removed > 0)
     }
 
-    pub fn get_file_name(&self) -> String {
-        format!("{}.txt", self.name_with_lane)
+    /// Keep the most recently used entries that fit the size limit.
+    fn evict_over_limit(&self) -> Result<(), String> {
+        let removed = self
+            .db
+            .execute(
+                "DELETE FROM builds WHERE rowid IN (
+                     SELECT rowid FROM (
+                         SELECT rowid, SUM(size) OVER (ORDER BY last_access DESC, rowid DESC) AS kept
+                         FROM builds
+                     ) WHERE kept > ?1
+                 )",
+                params![self.size_limit as i64],
+            )
+            .map_err(|err| err.to_string())?;
+        if removed > 0 {
+            info!("Evicted {} cache entries over the size limit.", removed);
+            self.db
+                .execute_batch("PRAGMA incremental_vacuum;")
+                .map_err(|err| err.to_string())?;
+        }
+        Ok(())
     }
+}
 
-    pub fn get_file_name_manual(name: &str, lane: Lane, mode: GameMode) -> String {
-        format!("{}-{:?}-{:?}.txt", name, lane, mode)
+/// Open the database and make sure it is usable with the current schema.
+fn open_db(path: &Path) -> Result<Connection, String> {
+    let db = Connection::open(path).map_err(|err| err.to_string())?;
+    let check: String = db
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|err| err.to_string())?;
+    if check != "ok" {
+        return Err(format!("Quick check failed: {}", check));
     }
 
-    pub fn is_expired(&self, duration: Duration) -> bool {
-        Utc::now().signed_duration_since(self.last_modified) >= duration
+    let version: i32 = db
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|err| err.to_string())?;
+    if version != SCHEMA_VERSION {
+        info!(
+            "Cache schema version is {}, migrate to {}.",
+            version, SCHEMA_VERSION
+        );
+        db.execute_batch(&format!(
+            "BEGIN;
+             DROP TABLE IF EXISTS builds;
+             CREATE TABLE builds (
+                 provider TEXT NOT NULL,
+                 champion TEXT NOT NULL,
+                 lane TEXT NOT NULL,
+                 mode TEXT NOT NULL,
+                 query TEXT NOT NULL,
+                 data TEXT NOT NULL,
+                 last_modified INTEGER NOT NULL,
+                 patch TEXT NOT NULL,
+                 size INTEGER NOT NULL,
+                 last_access INTEGER NOT NULL,
+                 PRIMARY KEY (provider, champion, lane, mode, query)
+             );
+             PRAGMA user_version = {};
+             COMMIT;
+             PRAGMA auto_vacuum = INCREMENTAL;
+             VACUUM;",
+            SCHEMA_VERSION
+        ))
+        .map_err(|err| err.to_string())?;
     }
+    Ok(db)
+}
 
-    pub fn update_last_modified(&mut self, new: DateTime<Utc>) {
-        self.last_modified = new;
+/// Old versions keep `cache.log` and `.txt` files in a directory per provider.
+fn remove_legacy_cache(dir: &Path) {
+    if dir.join("cache.log").is_file() {
+        info!("Remove legacy cache {:?}", dir);
+        if let Err(err) = fs::remove_dir_all(dir) {
+            error!("{:?}", err);
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,42 @@
+use std::{collections::HashMap, future::Future, sync::Mutex};
+
+use futures_util::future::{BoxFuture, FutureExt, Shared};
+
+type SharedRequest<T> = Shared<BoxFuture<'static, Result<T, String>>>;
+
+/// Lets concurrent requests with the same key join a single fetch
+/// instead of each starting their own.
+pub struct InFlight<T: Clone> {
+    requests: Mutex<HashMap<String, SharedRequest<T>>>,
+}
+
+impl<T: Clone + Send + Sync + 'static> InFlight<T> {
+    pub fn new() -> Self {
+        InFlight {
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `request` unless another request of `key` is running already,
+    /// then wait for that one instead.
+    pub async fn run<F>(&self, key: String, request: F) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, String>> + Send + 'static,
+    {
+        let shared = self
+            .requests
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| request.boxed().shared())
+            .clone();
+        let result = shared.await;
+
+        // Only the finished one, a new request may be running already.
+        let mut requests = self.requests.lock().unwrap();
+        if requests.get(&key).map_or(false, |r| r.peek().is_some()) {
+            requests.remove(&key);
+        }
+        result
+    }
+}
@@ -1,32 +1,121 @@
+use std::{
+    future::Future,
+    sync::{Arc, RwLock},
+};
+
 use async_trait::async_trait;
+use chrono::Utc;
 use futures_util::lock::Mutex;
-use jsonpath_rust::JsonPathFinder;
-use reqwest::Client;
 use scraper::{Html, Selector};
-use tracing::info;
+use serde::Deserialize;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use crate::{champion_id::ChampionId, http_client::HttpClient};
 
 use super::{
-    cache_manager::CacheManager, ChampionInfo, DynChampionInfo, FetchMode, GameMode, Lane,
+    cache_manager::{CacheData, CacheEntryInfo, CacheManager, CacheStats},
+    in_flight::InFlight,
+    schema::{DriftIssue, DriftReport, Schema},
+    BuildRecord, BuildUpdated, ChampionInfo, DynChampionInfo, FetchMode, GameMode, Lane,
     RuneItem, Source, SpellItem,
 };
 
 pub struct OPGG {
-    fetch_mode: FetchMode,
-    http_client: Client,
-    cache_manager: Mutex<CacheManager>,
+    fetch_mode: RwLock<FetchMode>,
+    http_client: HttpClient,
+    cache_manager: Arc<Mutex<CacheManager>>,
+    /// Running downloads by champion, lane and mode, to json of `BuildRecord`.
+    in_flight: Arc<InFlight<String>>,
+    updates: broadcast::Sender<BuildUpdated>,
+    drift: broadcast::Sender<DriftReport>,
 }
 
 #[derive(Debug, Clone)]
 pub struct OPGGChampionInfo {
-    next_data: String,
+    record: BuildRecord,
     is_cache: bool,
     lane: Lane,
-    champion_id: String,
+    patch: String,
+}
+
+/// `props.pageProps.data` of op.gg's `__NEXT_DATA__`, only the parts we use.
+#[derive(Debug, Deserialize)]
+struct OPGGData {
+    runes: Vec<OPGGRune>,
+    summoner_spells: Vec<OPGGSpell>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OPGGRune {
+    id: i64,
+    primary_page_id: i32,
+    primary_rune_ids: Vec<i32>,
+    secondary_page_id: i32,
+    secondary_rune_ids: Vec<i32>,
+    stat_mod_ids: Vec<i32>,
+    play: i32,
+    win: i32,
+    pick_rate: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OPGGSpell {
+    ids: Vec<i32>,
+    win: i32,
+    play: i32,
+    pick_rate: f64,
+}
+
+impl From<OPGGData> for BuildRecord {
+    fn from(data: OPGGData) -> Self {
+        BuildRecord {
+            runes: data
+                .runes
+                .into_iter()
+                .map(|r| RuneItem {
+                    id: r.id,
+                    primary_page_id: r.primary_page_id,
+                    primary_rune_ids: r.primary_rune_ids,
+                    secondary_page_id: r.secondary_page_id,
+                    secondary_rune_ids: r.secondary_rune_ids,
+                    stat_mod_ids: r.stat_mod_ids,
+                    play: r.play,
+                    win: r.win,
+                    pick_rate: r.pick_rate,
+                    name: String::new(),
+                    template_id: None,
+                })
+                .collect(),
+            spells: data
+                .summoner_spells
+                .into_iter()
+                .map(|s| SpellItem {
+                    ids: s.ids,
+                    win: s.win,
+                    play: s.play,
+                    pick_rate: s.pick_rate,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl OPGGChampionInfo {
+    fn new(data: CacheData, is_cache: bool, lane: Lane) -> Result<Self, String> {
+        let record = serde_json::from_str(&data.data)
+            .map_err(|err| format!("Parse build record error: {}", err))?;
+        Ok(OPGGChampionInfo {
+            record,
+            is_cache,
+            lane,
+            patch: data.patch,
+        })
+    }
 }
 
 #[async_trait]
 impl ChampionInfo for OPGGChampionInfo {
-    //remember add new function go modify format_data()
     fn is_cache(&self) -> bool {
         self.is_cache
     }
@@ -35,33 +124,18 @@ impl ChampionInfo for OPGGChampionInfo {
         self.lane
     }
 
+    fn get_patch(&self) -> String {
+        self.patch.clone()
+    }
+
     async fn get_runes(&self) -> Result<Vec<RuneItem>, String> {
-        let runes_finder =
-            JsonPathFinder::from_str(&self.next_data, "$.props.pageProps.data.runes[0:]")
-                .expect("Create runes finder failed!");
-        let rune_items = runes_finder.find();
-        let mut runes: Vec<RuneItem> = serde_json::from_value(rune_items).unwrap();
-        for mut r in &mut runes {
-            r.name = format!(
-                "[Lola] {}-{:?} ({:.2}%/{} games)",
-                self.champion_id,
-                self.lane,
-                r.win * 100 / r.play,
-                r.play
-            )
-        }
+        let runes = self.record.runes.clone();
         info!("runes len is {}", runes.len());
         Ok(runes)
     }
 
     async fn get_spells(&self) -> Result<Vec<SpellItem>, String> {
-        let spells_finder = JsonPathFinder::from_str(
-            &self.next_data,
-            "$.props.pageProps.data.summoner_spells[0:]",
-        )
-        .expect("Create spells finder failed!");
-        let spell_items = spells_finder.find();
-        let spells: Vec<SpellItem> = serde_json::from_value(spell_items).unwrap();
+        let spells = self.record.spells.clone();
         info!("spells len is {}", spells.len());
         Ok(spells)
     }
@@ -69,12 +143,12 @@ impl ChampionInfo for OPGGChampionInfo {
 
 #[async_trait]
 impl Source for OPGG {
-    fn set_fetch_mode(&mut self, mode: FetchMode) {
-        self.fetch_mode = mode;
+    fn set_fetch_mode(&self, mode: FetchMode) {
+        *self.fetch_mode.write().unwrap() = mode;
     }
 
     fn fetch_mode(&self) -> FetchMode {
-        self.fetch_mode
+        *self.fetch_mode.read().unwrap()
     }
 
     async fn set_expired(&self, days: i64) {
@@ -85,151 +159,297 @@ impl Source for OPGG {
         self.cache_manager.lock().await.get_expired()
     }
 
-    async fn clear_cache(&mut self) {
-        self.cache_manager.lock().await.clear_cache().await;
+    async fn set_patch(&self, patch: &str) {
+        self.cache_manager.lock().await.set_patch(patch)
     }
 
-    async fn get_champion_info(
+    async fn clear_cache(&self) {
+        if let Err(err) = self.cache_manager.lock().await.clear_cache() {
+            error!("Clear cache failed: {}", err);
+        }
+    }
+
+    async fn set_cache_size_limit(&self, bytes: u64) {
+        if let Err(err) = self.cache_manager.lock().await.set_size_limit(bytes) {
+            error!("Set cache size limit failed: {}", err);
+        }
+    }
+
+    async fn cache_stats(&self) -> Result<CacheStats, String> {
+        self.cache_manager.lock().await.stats()
+    }
+
+    async fn list_cache_entries(&self) -> Result<Vec<CacheEntryInfo>, String> {
+        self.cache_manager.lock().await.list_entries()
+    }
+
+    async fn evict_cache_entry(
         &self,
         champion_id: &str,
         lane: Lane,
         mode: GameMode,
+    ) -> Result<bool, String> {
+        self.cache_manager
+            .lock()
+            .await
+            .evict(champion_id, lane, mode)
+    }
+
+    async fn warm_cache(
+        &self,
+        champion: &ChampionId,
+        lane: Lane,
+        mode: GameMode,
+    ) -> Result<bool, String> {
+        let cache = self.cache_manager.lock().await;
+        if !cache.is_champion_data_old(&cache.key(champion.id(), lane, mode)) {
+            return Ok(false);
+        }
+        drop(cache);
+        self.download(champion, lane, mode, true).await?;
+        Ok(true)
+    }
+
+    async fn get_champion_info(
+        &self,
+        champion: &ChampionId,
+        lane: Lane,
+        mode: GameMode,
     ) -> Result<DynChampionInfo, String> {
-        match self.fetch_mode {
-            FetchMode::Online => Ok(Box::new(OPGGChampionInfo {
-                is_cache: false,
-                next_data: self
-                    .get_champion_data_online(champion_id, lane, mode)
-                    .await?,
-                lane,
-                champion_id: champion_id.to_owned(),
-            })),
-            FetchMode::Auto => {
-                if self
-                    .cache_manager
-                    .lock()
-                    .await
-                    .is_champion_data_old(champion_id, lane, mode)
-                {
-                    let mut next_data = self
-                        .get_champion_data_online(champion_id, lane, mode)
-                        .await?;
-                    let next_data = self.format_data(&mut next_data);
-                    self.cache_manager
-                        .lock()
-                        .await
-                        .update_champion(champion_id, lane, mode, &next_data)
-                        .await?;
-                    Ok(Box::new(OPGGChampionInfo {
-                        is_cache: false,
-                        next_data,
-                        lane,
-                        champion_id: champion_id.to_owned(),
-                    }))
-                } else {
-                    let next_data = self
-                        .cache_manager
-                        .lock()
-                        .await
-                        .get_champion_data(champion_id, lane, mode)
-                        .await?;
-                    Ok(Box::new(OPGGChampionInfo {
-                        is_cache: true,
-                        next_data,
-                        lane,
-                        champion_id: champion_id.to_owned(),
-                    }))
+        let data = match self.fetch_mode() {
+            FetchMode::Online => (self.download(champion, lane, mode, false).await?, false),
+            FetchMode::Offline => match self.get_cached(champion, lane, mode).await {
+                (Some(cached), _) => (cached, true),
+                (None, _) => return Err(format!("No cache of {} in offline mode.", champion)),
+            },
+            FetchMode::Auto => match self.get_cached(champion, lane, mode).await {
+                (Some(cached), false) => (cached, true),
+                (cached, _) => {
+                    match self.download(champion, lane, mode, true).await {
+                        Ok(data) => (data, false),
+                        Err(err) => match cached {
+                            Some(cached) => {
+                                error!("Download failed, use old cache instead: {}", err);
+                                (cached, true)
+                            }
+                            None => return Err(err),
+                        },
+                    }
                 }
-            }
-        }
+            },
+            FetchMode::StaleWhileRevalidate => match self.get_cached(champion, lane, mode).await {
+                (Some(cached), is_old) => {
+                    if is_old {
+                        self.revalidate(champion, lane, mode, cached.clone());
+                    }
+                    (cached, true)
+                }
+                (None, _) => (self.download(champion, lane, mode, true).await?, false),
+            },
+        };
+        Ok(Box::new(OPGGChampionInfo::new(data.0, data.1, lane)?))
+    }
+
+    fn subscribe_updates(&self) -> broadcast::Receiver<BuildUpdated> {
+        self.updates.subscribe()
+    }
+
+    fn subscribe_drift(&self) -> broadcast::Receiver<DriftReport> {
+        self.drift.subscribe()
     }
 }
 
 impl OPGG {
-    pub async fn new() -> Self {
-        OPGG {
-            fetch_mode: FetchMode::Auto,
-            http_client: reqwest::Client::builder().build().unwrap(),
-            cache_manager: Mutex::new(CacheManager::initialize().await.unwrap()),
+    pub async fn new(http_client: HttpClient) -> Result<Self, String> {
+        let cache_manager = CacheManager::initialize("opgg").or_else(|err| {
+            error!("Can't open the cache, builds are kept in memory only: {}", err);
+            CacheManager::in_memory("opgg")
+        })?;
+        Ok(OPGG {
+            fetch_mode: RwLock::new(FetchMode::Auto),
+            http_client,
+            cache_manager: Arc::new(Mutex::new(cache_manager)),
+            in_flight: Arc::new(InFlight::new()),
+            updates: broadcast::channel(16).0,
+            drift: broadcast::channel(16).0,
+        })
+    }
+
+    /// Cached data of champion if any, and whether it is old.
+    async fn get_cached(
+        &self,
+        champion: &ChampionId,
+        lane: Lane,
+        mode: GameMode,
+    ) -> (Option<CacheData>, bool) {
+        let cache = self.cache_manager.lock().await;
+        let key = cache.key(champion.id(), lane, mode);
+        let is_old = cache.is_champion_data_old(&key);
+        (cache.get_champion_data(&key).ok(), is_old)
+    }
+
+    /// Refresh the cache in background, tell subscribers if the data changed.
+    fn revalidate(&self, champion: &ChampionId, lane: Lane, mode: GameMode, cached: CacheData) {
+        let refresh = self.download(champion, lane, mode, true);
+        let updates = self.updates.clone();
+        let champion = champion.clone();
+        tokio::spawn(async move {
+            match refresh.await {
+                Ok(fresh) => {
+                    if fresh.data != cached.data {
+                        info!("Build of {} changed after revalidate.", champion);
+                        // No subscriber is fine.
+                        let _ = updates.send(BuildUpdated {
+                            champion,
+                            lane,
+                            mode,
+                        });
+                    }
+                }
+                Err(err) => error!("Revalidate {} failed: {}", champion, err),
+            }
+        });
+    }
+
+    /// Fetch the formatted data of champion, saving it to cache if `store`.
+    /// Concurrent downloads of the same data join the running one, whether they store or not.
+    fn download(
+        &self,
+        champion: &ChampionId,
+        lane: Lane,
+        mode: GameMode,
+        store: bool,
+    ) -> impl Future<Output = Result<CacheData, String>> + Send + 'static {
+        let key = format!("{}-{:?}-{:?}", champion, lane, mode);
+        let http_client = self.http_client.clone();
+        let cache_manager = self.cache_manager.clone();
+        let in_flight = self.in_flight.clone();
+        let drift = self.drift.clone();
+        let champion = champion.clone();
+        async move {
+            let fetched = champion.clone();
+            let record = in_flight
+                .run(key, async move {
+                    let next_data =
+                        OPGG::get_champion_data_online(&http_client, &champion, lane, mode)
+                            .await?;
+                    let record = OPGG::parse_next_data(&next_data).map_err(|issues| {
+                        let report = DriftReport {
+                            provider: String::from("opgg"),
+                            context: format!("{} {:?} {:?}", champion, lane, mode),
+                            detected_at: Utc::now(),
+                            issues,
+                        };
+                        for issue in &report.issues {
+                            error!("op.gg schema drift of {}: {}", report.context, issue);
+                        }
+                        // No subscriber is fine.
+                        let _ = drift.send(report);
+                        format!("Unexpected op.gg data of {}, it is not cached.", champion)
+                    })?;
+                    serde_json::to_string(&record).map_err(|err| err.to_string())
+                })
+                .await?;
+            let cache = cache_manager.lock().await;
+            if store {
+                cache.update_champion(&cache.key(fetched.id(), lane, mode), record)
+            } else {
+                Ok(CacheData {
+                    data: record,
+                    patch: cache.get_patch(),
+                })
+            }
         }
     }
 
-    async fn get_html(&self, url: &str) -> Result<String, String> {
-        self.http_client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| format!("Get request error: {}", e))?
-            .text()
-            .await
-            .map_err(|e| format!("Get html error: {}", e))
-    }
-
-    fn format_data(&self, data: &str) -> String {
-        let rune_items = JsonPathFinder::from_str(data, "$.props.pageProps.data.runes[0:]")
-            .expect("Create runes finder failed!")
-            .find();
-        let spell_items =
-            JsonPathFinder::from_str(data, "$.props.pageProps.data.summoner_spells[0:]")
-                .expect("Create spells finder failed!")
-                .find();
-        let cache = format!(
-            r#"{{
-                "props":{{
-                    "pageProps":{{
-                        "data":{{
-                            "runes": {},
-                            "summoner_spells": {}
-                        }}
-                    }}
-                }}
-            }}"#,
-            serde_json::to_string(&rune_items).unwrap(),
-            serde_json::to_string(&spell_items).unwrap()
-        );
-        cache
+    /// Read the build out of op.gg's `__NEXT_DATA__` json, checked against `OPGG::schema`.
+    fn parse_next_data(next_data: &str) -> Result<BuildRecord, Vec<DriftIssue>> {
+        let next_data: serde_json::Value = serde_json::from_str(next_data).map_err(|err| {
+            vec![DriftIssue::Unparsable {
+                error: err.to_string(),
+            }]
+        })?;
+        let issues = OPGG::schema().validate(&next_data);
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+        let data = next_data["props"]["pageProps"]["data"].clone();
+        let data: OPGGData = serde_json::from_value(data).map_err(|err| {
+            vec![DriftIssue::Unparsable {
+                error: err.to_string(),
+            }]
+        })?;
+        Ok(data.into())
+    }
+
+    /// Parts of `__NEXT_DATA__` that `OPGGData` reads.
+    fn schema() -> Schema {
+        let rune = Schema::Object(vec![
+            ("id", Schema::Integer),
+            ("primary_page_id", Schema::Integer),
+            ("primary_rune_ids", Schema::non_empty_array(Schema::Integer)),
+            ("secondary_page_id", Schema::Integer),
+            ("secondary_rune_ids", Schema::non_empty_array(Schema::Integer)),
+            ("stat_mod_ids", Schema::non_empty_array(Schema::Integer)),
+            ("play", Schema::Integer),
+            ("win", Schema::Integer),
+            ("pick_rate", Schema::Number),
+        ]);
+        let spell = Schema::Object(vec![
+            ("ids", Schema::non_empty_array(Schema::Integer)),
+            ("win", Schema::Integer),
+            ("play", Schema::Integer),
+            ("pick_rate", Schema::Number),
+        ]);
+        Schema::Object(vec![(
+            "props",
+            Schema::Object(vec![(
+                "pageProps",
+                Schema::Object(vec![(
+                    "data",
+                    // Rare lanes and new champions may have no builds at all.
+                    Schema::Object(vec![
+                        ("runes", Schema::array(rune)),
+                        ("summoner_spells", Schema::array(spell)),
+                    ]),
+                )]),
+            )]),
+        )])
     }
 
     async fn get_champion_data_online(
-        &self,
-        champion_id: &str,
+        http_client: &HttpClient,
+        champion: &ChampionId,
         lane: Lane,
         mode: GameMode,
     ) -> Result<String, String> {
-        let champion_id: String = champion_id
-            .chars()
-            .filter(|c| !is_illegal_for_opgg(c))
-            .collect(); //remove illegal characters.
+        let slug = champion.opgg_slug();
         let url = match mode {
             GameMode::Aram | GameMode::Urf => format!(
                 "https://www.op.gg/{}/{}/build",
                 mode_as_opgg(mode),
-                champion_id
+                slug
             ),
             GameMode::Classic => format!(
                 "https://www.op.gg/{}/{}/{}/build",
                 mode_as_opgg(mode),
-                champion_id,
+                slug,
                 lane.to_string()
             ),
         };
         info!("url is {}", &url);
-        let opgg_html = self.get_html(&url).await.expect("Get html failed!");
+        let opgg_html = http_client.get_text(&url).await?;
         // get runes
         let document = Html::parse_document(&opgg_html);
         let next_data = document
             .select(&Selector::parse("script#__NEXT_DATA__").unwrap())
             .next()
-            .expect("No found 'script#__NEXT_DATA__' element!")
+            .ok_or(String::from("No found 'script#__NEXT_DATA__' element!"))?
             .inner_html();
         Ok(next_data)
     }
 }
 
-fn is_illegal_for_opgg(c: &char) -> bool {
-    c == &'\'' || c.is_whitespace()
-}
-
 fn mode_as_opgg(mode: GameMode) -> &'static str {
     match mode {
         GameMode::Classic => "champions",
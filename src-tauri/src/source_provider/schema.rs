@@ -0,0 +1,260 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Expected shape of a provider payload, fields not listed are ignored.
+#[derive(Debug, Clone)]
+pub enum Schema {
+    Integer,
+    Number,
+    Array { items: Box<Schema>, non_empty: bool },
+    Object(Vec<(&'static str, Schema)>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JsonType {
+    Null,
+    Bool,
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum DriftIssue {
+    Missing { path: String },
+    /// A field is missing and unknown fields of its type showed up beside it.
+    Renamed { path: String, candidates: Vec<String> },
+    TypeChanged { path: String, expected: JsonType, found: JsonType },
+    Empty { path: String },
+    /// Not json at all.
+    Unparsable { error: String },
+}
+
+/// Payload of a provider that doesn't match the schema, it is not cached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub provider: String,
+    /// What was fetched, like the champion and lane.
+    pub context: String,
+    pub detected_at: DateTime<Utc>,
+    pub issues: Vec<DriftIssue>,
+}
+
+impl Schema {
+    pub fn array(items: Schema) -> Self {
+        Schema::Array {
+            items: Box::new(items),
+            non_empty: false,
+        }
+    }
+
+    pub fn non_empty_array(items: Schema) -> Self {
+        Schema::Array {
+            items: Box::new(items),
+            non_empty: true,
+        }
+    }
+
+    fn json_type(&self) -> JsonType {
+        match self {
+            Schema::Integer => JsonType::Integer,
+            Schema::Number => JsonType::Number,
+            Schema::Array { .. } => JsonType::Array,
+            Schema::Object(_) => JsonType::Object,
+        }
+    }
+
+    fn accepts(&self, found: JsonType) -> bool {
+        let expected = self.json_type();
+        expected == found || (expected == JsonType::Number && found == JsonType::Integer)
+    }
+
+    /// Every place the value differs from the schema, each path is reported once.
+    pub fn validate(&self, value: &Value) -> Vec<DriftIssue> {
+        let mut issues = vec![];
+        self.check(value, "$", &mut issues);
+        issues
+    }
+
+    fn check(&self, value: &Value, path: &str, issues: &mut Vec<DriftIssue>) {
+        let found = JsonType::of(value);
+        if !self.accepts(found) {
+            push_once(
+                issues,
+                DriftIssue::TypeChanged {
+                    path: path.to_owned(),
+                    expected: self.json_type(),
+                    found,
+                },
+            );
+            return;
+        }
+        match (self, value) {
+            (Schema::Array { items, non_empty }, Value::Array(values)) => {
+                if *non_empty && values.is_empty() {
+                    push_once(
+                        issues,
+                        DriftIssue::Empty {
+                            path: path.to_owned(),
+                        },
+                    );
+                }
+                let item_path = format!("{}[]", path);
+                for value in values {
+                    items.check(value, &item_path, issues);
+                }
+            }
+            (Schema::Object(fields), Value::Object(map)) => {
+                for (name, schema) in fields {
+                    let field_path = format!("{}.{}", path, name);
+                    match map.get(*name) {
+                        Some(value) => schema.check(value, &field_path, issues),
+                        None => {
+                            let candidates: Vec<String> = map
+                                .iter()
+                                .filter(|(key, value)| {
+                                    !fields.iter().any(|(name, _)| name == key)
+                                        && schema.accepts(JsonType::of(value))
+                                })
+                                .map(|(key, _)| key.clone())
+                                .collect();
+                            let issue = if candidates.is_empty() {
+                                DriftIssue::Missing { path: field_path }
+                            } else {
+                                DriftIssue::Renamed {
+                                    path: field_path,
+                                    candidates,
+                                }
+                            };
+                            push_once(issues, issue);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl JsonType {
+    fn of(value: &Value) -> Self {
+        match value {
+            Value::Null => JsonType::Null,
+            Value::Bool(_) => JsonType::Bool,
+            Value::Number(n) if n.is_i64() || n.is_u64() => JsonType::Integer,
+            Value::Number(_) => JsonType::Number,
+            Value::String(_) => JsonType::String,
+            Value::Array(_) => JsonType::Array,
+            Value::Object(_) => JsonType::Object,
+        }
+    }
+}
+
+impl fmt::Display for DriftIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DriftIssue::Missing { path } => write!(f, "{} is missing", path),
+            DriftIssue::Renamed { path, candidates } => {
+                write!(f, "{} is missing, renamed to one of {:?}?", path, candidates)
+            }
+            DriftIssue::TypeChanged {
+                path,
+                expected,
+                found,
+            } => write!(f, "{} is {:?} instead of {:?}", path, found, expected),
+            DriftIssue::Empty { path } => write!(f, "{} is empty", path),
+            DriftIssue::Unparsable { error } => write!(f, "unparsable: {}", error),
+        }
+    }
+}
+
+fn push_once(issues: &mut Vec<DriftIssue>, issue: DriftIssue) {
+    if !issues.contains(&issue) {
+        issues.push(issue);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::Object(vec![(
+            "runes",
+            Schema::array(Schema::Object(vec![
+                ("ids", Schema::non_empty_array(Schema::Integer)),
+                ("pick_rate", Schema::Number),
+            ])),
+        )])
+    }
+
+    #[test]
+    fn matching_payload() {
+        let value = json!({ "runes": [{ "ids": [1, 2], "pick_rate": 1, "extra": "x" }] });
+        assert_eq!(schema().validate(&value), vec![]);
+        assert_eq!(schema().validate(&json!({ "runes": [] })), vec![]);
+    }
+
+    #[test]
+    fn missing() {
+        let value = json!({ "runes": [{ "ids": [1] }, { "ids": [2] }] });
+        assert_eq!(
+            schema().validate(&value),
+            vec![DriftIssue::Missing {
+                path: String::from("$.runes[].pick_rate")
+            }]
+        );
+    }
+
+    #[test]
+    fn renamed() {
+        let value = json!({ "runes": [{ "ids": [1], "pickRate": 0.5, "name": "x" }] });
+        assert_eq!(
+            schema().validate(&value),
+            vec![DriftIssue::Renamed {
+                path: String::from("$.runes[].pick_rate"),
+                candidates: vec![String::from("pickRate")],
+            }]
+        );
+    }
+
+    #[test]
+    fn type_changed() {
+        let value = json!({ "runes": [{ "ids": ["1"], "pick_rate": 0.5 }] });
+        assert_eq!(
+            schema().validate(&value),
+            vec![DriftIssue::TypeChanged {
+                path: String::from("$.runes[].ids[]"),
+                expected: JsonType::Integer,
+                found: JsonType::String,
+            }]
+        );
+        assert_eq!(
+            schema().validate(&json!({ "runes": {} })),
+            vec![DriftIssue::TypeChanged {
+                path: String::from("$.runes"),
+                expected: JsonType::Array,
+                found: JsonType::Object,
+            }]
+        );
+    }
+
+    #[test]
+    fn empty_inner_list() {
+        let value = json!({ "runes": [{ "ids": [], "pick_rate": 0.5 }] });
+        assert_eq!(
+            schema().validate(&value),
+            vec![DriftIssue::Empty {
+                path: String::from("$.runes[].ids")
+            }]
+        );
+    }
+}
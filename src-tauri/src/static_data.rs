@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ddragon::{Champion, GameMap, Item, Language, Queue, RuneTree, SummonerSpell},
+    http_client::HttpClient,
+};
+
+pub mod cdragon;
+
+/// Where static data comes from. A backend only implements the kinds it has,
+/// the others fail as unsupported.
+#[async_trait]
+pub trait StaticData: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Keyed by the numeric champion key, like `"62"`.
+    async fn get_champions(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<HashMap<String, Champion>, String> {
+        Err(self.unsupported(DataKind::Champions))
+    }
+
+    async fn get_rune_trees(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<Vec<RuneTree>, String> {
+        Err(self.unsupported(DataKind::RuneTrees))
+    }
+
+    /// Keyed by the numeric spell id.
+    async fn get_summoner_spells(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<HashMap<i32, SummonerSpell>, String> {
+        Err(self.unsupported(DataKind::SummonerSpells))
+    }
+
+    async fn get_items(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<HashMap<i32, Item>, String> {
+        Err(self.unsupported(DataKind::Items))
+    }
+
+    async fn get_maps(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<Vec<GameMap>, String> {
+        Err(self.unsupported(DataKind::Maps))
+    }
+
+    async fn get_queues(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<Vec<Queue>, String> {
+        Err(self.unsupported(DataKind::Queues))
+    }
+
+    async fn get_stat_shards(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<Vec<StatShard>, String> {
+        Err(self.unsupported(DataKind::StatShards))
+    }
+
+    /// Augments of Arena.
+    async fn get_augments(
+        &self,
+        _http: &HttpClient,
+        _version: &str,
+        _language: Language,
+    ) -> Result<Vec<Augment>, String> {
+        Err(self.unsupported(DataKind::Augments))
+    }
+
+    fn unsupported(&self, kind: DataKind) -> String {
+        format!("{} has no {:?}", self.name(), kind)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DataKind {
+    Champions,
+    RuneTrees,
+    SummonerSpells,
+    Items,
+    Maps,
+    Queues,
+    StatShards,
+    Augments,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    DDragon,
+    CommunityDragon,
+}
+
+/// Backend of each kind of data, kinds not listed use `DataKind::default_backend`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Routing(HashMap<DataKind, Backend>);
+
+impl DataKind {
+    /// DDragon unless it lacks the data.
+    pub fn default_backend(&self) -> Backend {
+        match self {
+            DataKind::StatShards | DataKind::Augments => Backend::CommunityDragon,
+            _ => Backend::DDragon,
+        }
+    }
+}
+
+impl Routing {
+    pub fn backend(&self, kind: DataKind) -> Backend {
+        self.0
+            .get(&kind)
+            .copied()
+            .unwrap_or_else(|| kind.default_backend())
+    }
+}
+
+/// A stat shard, which ddragon doesn't list with the runes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatShard {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    /// Path served by the asset cache, like `cdn/img/perk-images/...`.
+    pub icon: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Augment {
+    pub id: i32,
+    pub name: String,
+    pub description: String,
+    /// Path served by the asset cache.
+    pub icon: String,
+    /// 0 silver, 1 gold, 2 prismatic.
+    pub rarity: i32,
+}
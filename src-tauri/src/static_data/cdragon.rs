@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{Augment, StatShard, StaticData};
+use crate::{
+    ddragon::{patch_of, Language, Queue},
+    http_client::HttpClient,
+};
+
+const CDRAGON_URL: &str = "https://raw.communitydragon.org";
+/// Prefix of asset paths in the game data of the client.
+const CLIENT_ASSETS: &str = "/lol-game-data/assets/v1/";
+
+/// Data extracted from the game client by CommunityDragon, it has what ddragon lacks.
+#[derive(Debug, Default)]
+pub struct CommunityDragon;
+
+#[async_trait]
+impl StaticData for CommunityDragon {
+    fn name(&self) -> &'static str {
+        "CommunityDragon"
+    }
+
+    async fn get_queues(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Vec<Queue>, String> {
+        let json = http
+            .get_text(&game_data_url(version, language, "queues.json"))
+            .await?;
+        parse_queues(&json)
+    }
+
+    async fn get_stat_shards(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Vec<StatShard>, String> {
+        let json = http
+            .get_text(&game_data_url(version, language, "perks.json"))
+            .await?;
+        parse_stat_shards(&json, &patch_of(version))
+    }
+
+    async fn get_augments(
+        &self,
+        http: &HttpClient,
+        version: &str,
+        language: Language,
+    ) -> Result<Vec<Augment>, String> {
+        let url = format!(
+            "{}/{}/cdragon/arena/{}.json",
+            CDRAGON_URL,
+            patch_of(version),
+            language.to_string().to_lowercase()
+        );
+        let json = http.get_text(&url).await?;
+        parse_augments(&json, &patch_of(version))
+    }
+}
+
+/// Locale folder of the client data, english is `default`.
+fn locale_of(language: Language) -> String {
+    match language {
+        Language::en_US => String::from("default"),
+        _ => language.to_string().to_lowercase(),
+    }
+}
+
+fn game_data_url(version: &str, language: Language, file: &str) -> String {
+    format!(
+        "{}/{}/plugins/rcp-be-lol-game-data/global/{}/v1/{}",
+        CDRAGON_URL,
+        patch_of(version),
+        locale_of(language),
+        file
+    )
+}
+
+/// Asset path of an icon in the client data, CommunityDragon keeps files lowercase.
+fn client_asset_path(patch: &str, icon_path: &str) -> String {
+    let icon = icon_path.strip_prefix(CLIENT_ASSETS).unwrap_or(icon_path);
+    format!(
+        "cdragon/{}/plugins/rcp-be-lol-game-data/global/default/v1/{}",
+        patch,
+        icon.trim_start_matches('/').to_lowercase()
+    )
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Perk {
+    id: i32,
+    name: String,
+    #[serde(default)]
+    short_desc: String,
+    #[serde(default)]
+    icon_path: String,
+}
+
+fn parse_stat_shards(json: &str, patch: &str) -> Result<Vec<StatShard>, String> {
+    let perks: Vec<Perk> = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let mut shards: Vec<StatShard> = perks
+        .into_iter()
+        // Stat shards are the 5000s, runes are 8000s and 9000s.
+        .filter(|perk| (5000..6000).contains(&perk.id))
+        .map(|perk| StatShard {
+            id: perk.id,
+            name: perk.name,
+            description: perk.short_desc,
+            icon: client_asset_path(patch, &perk.icon_path),
+        })
+        .collect();
+    shards.sort_by_key(|shard| shard.id);
+    Ok(shards)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CDragonQueue {
+    id: i32,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    detailed_description: String,
+}
+
+/// `queues.json` was a list and is keyed by id in newer patches.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum QueuesJson {
+    List(Vec<CDragonQueue>),
+    Map(HashMap<String, CDragonQueue>),
+}
+
+fn parse_queues(json: &str) -> Result<Vec<Queue>, String> {
+    let queues = match serde_json::from_str(json).map_err(|err| err.to_string())? {
+        QueuesJson::List(queues) => queues,
+        QueuesJson::Map(queues) => queues.into_values().collect(),
+    };
+    let non_empty = |s: String| (!s.is_empty()).then_some(s);
+    let mut queues: Vec<Queue> = queues
+        .into_iter()
+        .map(|queue| Queue {
+            queue_id: queue.id,
+            // The client data names queues but not their maps.
+            map: String::new(),
+            name: non_empty(queue.name),
+            description: non_empty(queue.description),
+            notes: non_empty(queue.detailed_description),
+        })
+        .collect();
+    queues.sort_by_key(|queue| queue.queue_id);
+    Ok(queues)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CDragonAugment {
+    id: i32,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    icon_large: String,
+    #[serde(default)]
+    rarity: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArenaJson {
+    augments: Vec<CDragonAugment>,
+}
+
+fn parse_augments(json: &str, patch: &str) -> Result<Vec<Augment>, String> {
+    let arena: ArenaJson = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let mut augments: Vec<Augment> = arena
+        .augments
+        .into_iter()
+        .map(|augment| Augment {
+            id: augment.id,
+            name: augment.name,
+            description: augment.desc,
+            icon: format!("cdragon/{}/game/{}", patch, augment.icon_large.to_lowercase()),
+            rarity: augment.rarity,
+        })
+        .collect();
+    augments.sort_by_key(|augment| augment.id);
+    Ok(augments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERKS: &str = include_str!("../../tests/fixtures/cdragon/perks.json");
+    const QUEUES: &str = include_str!("../../tests/fixtures/cdragon/queues.json");
+    const ARENA: &str = include_str!("../../tests/fixtures/cdragon/arena.json");
+
+    #[test]
+    fn stat_shards_skip_runes() {
+        let shards = parse_stat_shards(PERKS, "13.5").unwrap();
+        let ids: Vec<i32> = shards.iter().map(|shard| shard.id).collect();
+        assert_eq!(ids, vec![5001, 5008, 5011]);
+        assert_eq!(shards[1].name, "Adaptive Force");
+        assert_eq!(
+            shards[1].icon,
+            "cdragon/13.5/plugins/rcp-be-lol-game-data/global/default/v1/perk-images/statmods/statmodsadaptiveforceicon.png"
+        );
+    }
+
+    #[test]
+    fn queues_keep_names_and_notes() {
+        let queues = parse_queues(QUEUES).unwrap();
+        assert_eq!(queues.len(), 3);
+        assert_eq!(queues[0].queue_id, 420);
+        assert_eq!(queues[0].name.as_deref(), Some("Ranked Solo/Duo"));
+        assert_eq!(queues[0].description.as_deref(), Some("5v5 Ranked Solo"));
+        assert_eq!(queues[0].notes, None);
+        assert_eq!(queues[2].queue_id, 1700);
+        assert!(queues[2].notes.is_some());
+    }
+
+    #[test]
+    fn queues_keyed_by_id_are_read_too() {
+        let keyed = r#"{"450": {"id": 450, "name": "ARAM", "description": "5v5 ARAM"}}"#;
+        let queues = parse_queues(keyed).unwrap();
+        assert_eq!(queues[0].queue_id, 450);
+    }
+
+    #[test]
+    fn augments_point_to_game_assets() {
+        let augments = parse_augments(ARENA, "13.5").unwrap();
+        assert_eq!(augments.len(), 2);
+        assert_eq!(augments[0].name, "Typhoon");
+        assert_eq!(augments[0].rarity, 2);
+        assert_eq!(
+            augments[0].icon,
+            "cdragon/13.5/game/assets/ux/cherry/augments/icons/typhoon_large.png"
+        );
+    }
+
+    #[test]
+    fn broken_json_is_an_error() {
+        assert!(parse_stat_shards("{", "13.5").is_err());
+        assert!(parse_augments(r#"{"augments": 1}"#, "13.5").is_err());
+    }
+
+    #[test]
+    fn english_is_the_default_locale() {
+        assert_eq!(locale_of(Language::en_US), "default");
+        assert_eq!(locale_of(Language::ko_KR), "ko_kr");
+    }
+}
@@ -1,11 +1,20 @@
+use futures_util::lock::Mutex;
 use tauri::{CustomMenuItem, SystemTrayMenu, SystemTrayMenuItem, SystemTray, AppHandle, SystemTrayEvent, Manager};
+use tracing::error;
+
+use crate::{app_config::AppConfig, cache_warmer::CacheWarmer};
 
 pub fn create_tray() -> SystemTray {
     let show = CustomMenuItem::new("show".to_string(), "Show the app");
+    let warm_cache = CustomMenuItem::new("warm_cache".to_string(), "Warm cache");
+    let stop_warm_cache = CustomMenuItem::new("stop_warm_cache".to_string(), "Stop warming cache");
     let quit = CustomMenuItem::new("quit".to_string(), "Quit");
     let tray_menu = SystemTrayMenu::new()
         .add_item(show)
         .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(warm_cache)
+        .add_item(stop_warm_cache)
+        .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(quit);
 
     SystemTray::new().with_menu(tray_menu)
@@ -16,6 +25,8 @@ pub fn handle_event(handle: &AppHandle, event: SystemTrayEvent) {
         SystemTrayEvent::MenuItemClick { id, .. } => {
             match id.as_str() {
                 "show"=> show_the_main_app(&handle),
+                "warm_cache"=> start_cache_warmer(handle),
+                "stop_warm_cache"=> handle.state::<CacheWarmer>().stop(),
                 "quit"=> {
                     handle.exit(0);
                 },
@@ -27,6 +38,16 @@ pub fn handle_event(handle: &AppHandle, event: SystemTrayEvent) {
     }
 }
 
+fn start_cache_warmer(handle: &AppHandle) {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let options = handle.state::<Mutex<AppConfig>>().lock().await.warmer_options();
+        if let Err(err) = handle.state::<CacheWarmer>().start(handle.clone(), options) {
+            error!(err);
+        }
+    });
+}
+
 fn show_the_main_app(handle: &AppHandle) {
     let main = handle.get_window("main").unwrap();
                     main.show().unwrap();
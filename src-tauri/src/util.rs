@@ -1,17 +1,26 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use tokio::{fs::File, io::AsyncWriteExt};
 
-pub async fn write_to_file(path: &Path, data: &str) -> Result<(), String> {
-    let file = File::create(path).await;
-
-    match file {
-        Ok(mut file) => {
-            file.write_all(data.as_bytes())
-                .await
-                .map_err(|err| err.to_string())?;
-            Ok(())
-        }
-        Err(err) => Err(err.to_string()),
+/// Write to a temporary file next to `path` and move it over `path`, so a crash never
+/// leaves half a file. With `backup` the replaced file is kept as `<name>.bak`.
+pub async fn write_atomic(path: &Path, data: &[u8], backup: bool) -> Result<(), String> {
+    let with_suffix = |suffix: &str| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(suffix);
+        PathBuf::from(name)
+    };
+    let tmp = with_suffix(".tmp");
+    let mut file = File::create(&tmp).await.map_err(|err| err.to_string())?;
+    file.write_all(data).await.map_err(|err| err.to_string())?;
+    file.sync_all().await.map_err(|err| err.to_string())?;
+    drop(file);
+    if backup && path.is_file() {
+        tokio::fs::copy(path, with_suffix(".bak"))
+            .await
+            .map_err(|err| err.to_string())?;
     }
-}
\ No newline at end of file
+    tokio::fs::rename(&tmp, path)
+        .await
+        .map_err(|err| err.to_string())
+}
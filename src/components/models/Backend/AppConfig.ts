@@ -3,6 +3,19 @@ export interface AppConfig {
     expired_days: number,
     auto_select_lane: boolean,
     mini_mode: boolean,
+    cache_size_limit_mb: number,
+    language: string,
+    http_timeout_secs: number,
+    http_max_retries: number,
+    http_requests_per_second: number,
+    proxy: string,
+    no_proxy: string[],
+    extra_ca_certs: string[],
+    /** Empty to keep them in the data path. */
+    custom_builds_path?: string | null,
+    warmer_modes: string[],
+    warmer_concurrency: number,
+    warmer_interval_ms: number,
     cache_path: string,
     config_path: string,
     data_path: string,
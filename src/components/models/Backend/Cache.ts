@@ -0,0 +1,17 @@
+export interface CacheStats {
+    entries: number,
+    size: number,
+    size_limit: number,
+}
+
+export interface CacheEntryInfo {
+    provider: string,
+    champion: string,
+    lane: string,
+    mode: string,
+    query: string,
+    patch: string,
+    size: number,
+    last_modified: string,
+    last_access: string,
+}
\ No newline at end of file
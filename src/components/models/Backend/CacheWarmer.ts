@@ -0,0 +1,6 @@
+export interface WarmerProgress {
+    done: number,
+    total: number,
+    failed: number,
+    running: boolean,
+}
\ No newline at end of file
@@ -1,3 +1,5 @@
+import { LolRuneItem } from "../LOL/LolRuneItem";
+
 export interface RuneItem {
     id: number,
     primary_page_id: number,
@@ -9,6 +11,8 @@ export interface RuneItem {
     win: number,
     pick_rate: number,
     name: string,
+    /** Set on custom runes that come from a rune template. */
+    template_id?: number | null,
 }
 
 export interface SpellItem {
@@ -18,10 +22,96 @@ export interface SpellItem {
     pick_rate: number,
 }
 
+export interface ItemBlock {
+    name: string,
+    items: number[],
+}
+
+export type Skill = "Q" | "W" | "E" | "R";
+
 export interface Build {
     runes: RuneItem[],
     is_custom: boolean,
     spells: SpellItem[],
     is_cache: boolean,
     lane: string,
-}
\ No newline at end of file
+    patch: string,
+    rejected: string[],
+    unchecked?: boolean,
+    items: ItemBlock[],
+    skill_order: Skill[],
+    notes: string,
+}
+
+export interface CustomRunePage {
+    /** Stays the same on edits. */
+    id: number,
+    page: LolRuneItem,
+    lanes: string[],
+    /** Ddragon ids of champions the page is meant against. */
+    matchups: string[],
+    last_used?: string,
+}
+
+export interface CustomBuild {
+    version: number,
+    pages: CustomRunePage[],
+    next_page_id: number,
+    /** Pairs of summoner spell ids. */
+    spells: number[][],
+    items: ItemBlock[],
+    skill_order: Skill[],
+    lanes: string[],
+    notes: string,
+}
+
+export interface SharedBuild {
+    champion: string,
+    mode: string,
+    build: CustomBuild,
+}
+
+export type ImportSource = { kind: "Code", value: string } | { kind: "Archive", value: string };
+
+/** What to do with an imported rune page whose name is taken. */
+export type Conflict = "Overwrite" | "Rename" | "Skip";
+
+export interface ImportConflict {
+    file: string,
+    names: string[],
+}
+
+export interface ImportReport {
+    added: string[],
+    overwritten: string[],
+    renamed: string[],
+    skipped: string[],
+    rejected: string[],
+}
+
+/** Payload of `custom_builds_changed`, for a build changed outside Lola. */
+export interface CustomBuildChange {
+    champion_id: string,
+    game_mode: string,
+    removed: boolean,
+}
+
+/** What a champion changes of a template, the rest follows the template. */
+export interface TemplateOverride {
+    name?: string | null,
+    primary_style_id?: number | null,
+    sub_style_id?: number | null,
+    selected_perk_ids?: number[] | null,
+}
+
+export interface TemplateLink {
+    champion: string,
+    mode: string,
+    overrides: TemplateOverride,
+}
+
+export interface RuneTemplate {
+    id: number,
+    page: LolRuneItem,
+    links: TemplateLink[],
+}
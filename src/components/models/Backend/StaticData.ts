@@ -0,0 +1,56 @@
+export interface StaticInfo {
+    id: number,
+    name: string,
+    description: string,
+    icon: string,
+}
+
+export interface ChampionMatch {
+    key: number,
+    id: string,
+    name: string,
+    score: number,
+}
+
+export interface Named {
+    id: string,
+    name: string,
+}
+
+export interface StatChange {
+    stat: string,
+    old: number | null,
+    new: number | null,
+}
+
+export interface Changed extends Named {
+    changes: StatChange[],
+}
+
+export interface RuneChange {
+    id: number,
+    name: string,
+    old: string,
+    new: string,
+}
+
+export interface VersionDiff {
+    old: string,
+    new: string,
+    added_champions: Named[],
+    removed_champions: Named[],
+    champions: Changed[],
+    added_items: Named[],
+    removed_items: Named[],
+    items: Changed[],
+    runes: RuneChange[],
+}
+
+export interface Augment {
+    id: number,
+    name: string,
+    description: string,
+    icon: string,
+    /** 0 silver, 1 gold, 2 prismatic. */
+    rarity: number,
+}
@@ -1,5 +1,7 @@
 import { invoke } from "@tauri-apps/api";
 import { AppConfig } from "../models/Backend/AppConfig";
+import { WarmerProgress } from "../models/Backend/CacheWarmer";
+import { CacheEntryInfo, CacheStats } from "../models/Backend/Cache";
 
 export async function saveAppConfig() {
     await invoke("save_app_config")
@@ -14,4 +16,32 @@ export async function setAppConfig(newConfig: AppConfig) {
 
 export async function getAppConfig(): Promise<AppConfig> {
     return await invoke<AppConfig>("get_app_config");
+}
+
+export async function startCacheWarmer() {
+    await invoke("start_cache_warmer");
+}
+
+export async function stopCacheWarmer() {
+    await invoke("stop_cache_warmer");
+}
+
+export async function getCacheWarmerProgress(): Promise<WarmerProgress> {
+    return await invoke<WarmerProgress>("get_cache_warmer_progress");
+}
+
+export async function getCacheStats(): Promise<CacheStats> {
+    return await invoke<CacheStats>("cache_stats");
+}
+
+export async function listCacheEntries(): Promise<CacheEntryInfo[]> {
+    return await invoke<CacheEntryInfo[]>("list_cache_entries");
+}
+
+export async function evictCacheEntry(championId: string, lane: string, gameMode: string): Promise<boolean> {
+    return await invoke<boolean>("evict_cache_entry", {
+        championId,
+        lane,
+        gameMode
+    });
 }
\ No newline at end of file
@@ -1,10 +1,11 @@
 import { invoke } from "@tauri-apps/api";
 import { LolRuneItem } from "../models/LOL/LolRuneItem";
-import { Build, RuneItem, SpellItem } from "../models/Backend/SelectChampion";
+import { Build, Conflict, CustomBuild, ImportConflict, ImportReport, ImportSource, RuneItem, RuneTemplate, SpellItem, TemplateOverride } from "../models/Backend/SelectChampion";
 import { GameMode } from "../models/LOL/gameMode";
 import { SummonerInfo } from "../models/LOL/SummonerInfo";
 import { currentSummoner } from "./global";
 import { ChampionInfo } from "../models/LOL/ChampionInfo";
+import { ChampionMatch } from "../models/Backend/StaticData";
 
 export function lget<T>(url: string): Promise<T> {
     return invoke<T>("lcu_get", {
@@ -46,6 +47,13 @@ export async function getChampionIconUrl(championId: string): Promise<string> {
     return icon;
 }
 
+export async function searchChampions(query: string, limit?: number): Promise<ChampionMatch[]> {
+    return await invoke("search_champions", {
+        query,
+        limit
+    });
+}
+
 export async function isChampionSelecting(): Promise<boolean> {
     return (await lget<string>("/lol-gameflow/v1/gameflow-phase")) == "ChampSelect";
 }
@@ -104,7 +112,8 @@ export async function getChampionAllBuild(championId: string, gameMode: string):
       });
 }
 
-export async function addChampionCustomRune(championId: string, gameMode: string, runeItem: LolRuneItem): Promise<boolean> {
+/** Returns the id of the added page. */
+export async function addChampionCustomRune(championId: string, gameMode: string, runeItem: LolRuneItem): Promise<number> {
     return await invoke("add_champion_custom_rune", {
         championId,
         gameMode,
@@ -112,7 +121,149 @@ export async function addChampionCustomRune(championId: string, gameMode: string
     });
 }
 
-export async function removeChampionCustomRune(championId: string, gameMode: string, runeName: string): Promise<boolean> {
+export async function getCustomBuild(championId: string, gameMode: string): Promise<CustomBuild> {
+    return await invoke("get_custom_build", {
+        championId,
+        gameMode
+    });
+}
+
+export async function saveCustomBuild(championId: string, gameMode: string, build: CustomBuild): Promise<void> {
+    return await invoke("save_custom_build", {
+        championId,
+        gameMode,
+        build
+    });
+}
+
+export async function exportCustomBuildCode(championId: string, gameMode: string): Promise<string> {
+    return await invoke("export_custom_build_code", {
+        championId,
+        gameMode
+    });
+}
+
+export async function exportCustomBuildsArchive(path: string): Promise<number> {
+    return await invoke("export_custom_builds_archive", {
+        path
+    });
+}
+
+export async function previewCustomBuildsImport(source: ImportSource): Promise<ImportConflict[]> {
+    return await invoke("preview_custom_builds_import", {
+        source
+    });
+}
+
+export async function importCustomBuilds(source: ImportSource, conflict: Conflict): Promise<ImportReport> {
+    return await invoke("import_custom_builds", {
+        source,
+        conflict
+    });
+}
+
+export async function updateCustomRunePage(championId: string, gameMode: string, pageId: number, runeItem: LolRuneItem): Promise<void> {
+    return await invoke("update_custom_rune_page", {
+        championId,
+        gameMode,
+        pageId,
+        runeItem
+    });
+}
+
+export async function renameCustomRunePage(championId: string, gameMode: string, pageId: number, name: string): Promise<void> {
+    return await invoke("rename_custom_rune_page", {
+        championId,
+        gameMode,
+        pageId,
+        name
+    });
+}
+
+export async function reorderCustomRunePages(championId: string, gameMode: string, pageIds: number[]): Promise<void> {
+    return await invoke("reorder_custom_rune_pages", {
+        championId,
+        gameMode,
+        pageIds
+    });
+}
+
+export async function tagCustomRunePage(championId: string, gameMode: string, pageId: number, lanes: string[], matchups: string[]): Promise<void> {
+    return await invoke("tag_custom_rune_page", {
+        championId,
+        gameMode,
+        pageId,
+        lanes,
+        matchups
+    });
+}
+
+export async function markCustomRunePageUsed(championId: string, gameMode: string, pageId: number): Promise<void> {
+    return await invoke("mark_custom_rune_page_used", {
+        championId,
+        gameMode,
+        pageId
+    });
+}
+
+export async function removeCustomRunePage(championId: string, gameMode: string, pageId: number): Promise<void> {
+    return await invoke("remove_custom_rune_page", {
+        championId,
+        gameMode,
+        pageId
+    });
+}
+
+export async function getRuneTemplates(): Promise<RuneTemplate[]> {
+    return await invoke("get_rune_templates");
+}
+
+/** Returns the id of the added template. */
+export async function addRuneTemplate(runeItem: LolRuneItem): Promise<number> {
+    return await invoke("add_rune_template", {
+        runeItem
+    });
+}
+
+export async function updateRuneTemplate(templateId: number, runeItem: LolRuneItem): Promise<void> {
+    return await invoke("update_rune_template", {
+        templateId,
+        runeItem
+    });
+}
+
+export async function removeRuneTemplate(templateId: number): Promise<void> {
+    return await invoke("remove_rune_template", {
+        templateId
+    });
+}
+
+export async function linkRuneTemplate(templateId: number, championId: string, gameMode: string): Promise<void> {
+    return await invoke("link_rune_template", {
+        templateId,
+        championId,
+        gameMode
+    });
+}
+
+export async function unlinkRuneTemplate(templateId: number, championId: string, gameMode: string): Promise<void> {
+    return await invoke("unlink_rune_template", {
+        templateId,
+        championId,
+        gameMode
+    });
+}
+
+export async function setRuneTemplateOverride(templateId: number, championId: string, gameMode: string, overrides: TemplateOverride): Promise<void> {
+    return await invoke("set_rune_template_override", {
+        templateId,
+        championId,
+        gameMode,
+        overrides
+    });
+}
+
+export async function removeChampionCustomRune(championId: string, gameMode: string, runeName: string): Promise<void> {
     return await invoke("remove_champion_custom_rune", {
         championId,
         gameMode,
@@ -120,7 +271,7 @@ export async function removeChampionCustomRune(championId: string, gameMode: str
     });
 }
 
-export async function removeChampionCustomRunes(championId: string, gameMode: string): Promise<boolean> {
+export async function removeChampionCustomRunes(championId: string, gameMode: string): Promise<void> {
     return await invoke("remove_champion_custom_runes", {
         championId,
         gameMode
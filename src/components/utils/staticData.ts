@@ -0,0 +1,40 @@
+import { invoke } from "@tauri-apps/api";
+import { listen } from "@tauri-apps/api/event";
+import { ref } from "vue";
+import { Augment, StaticInfo, VersionDiff } from "../models/Backend/StaticData";
+
+let runeInfos = ref<Record<number, StaticInfo>>({});
+let spellInfos = ref<Record<number, StaticInfo>>({});
+let loaded = false;
+
+export async function loadStaticData() {
+    if (loaded) {
+        return;
+    }
+    runeInfos.value = await invoke("get_rune_infos");
+    spellInfos.value = await invoke("get_spell_infos");
+    loaded = true;
+}
+
+// A newer ddragon is in use, load again.
+listen<string>("ddragon_updated", async () => {
+    loaded = false;
+    await loadStaticData();
+});
+
+export async function getAugments(): Promise<Augment[]> {
+    return await invoke("get_augments");
+}
+
+export async function getDDragonVersions(): Promise<string[]> {
+    return await invoke("get_ddragon_versions");
+}
+
+export async function diffVersions(old: string, newVersion: string): Promise<VersionDiff> {
+    return await invoke("diff_versions", {
+        old,
+        new: newVersion
+    });
+}
+
+export { runeInfos, spellInfos };